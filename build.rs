@@ -0,0 +1,7 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+        tonic_build::compile_protos("proto/connect4.proto").unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+// analyze [MOVES] [MS] [--searchmoves COLUMNS | --exclude COLUMNS]: searches the position after
+// MOVES (the columns played from the empty board, `4453`) and lists every legal move with its
+// statistics, or only the moves of COLUMNS (`345`) with --searchmoves, while --exclude looks
+// for the best move but those of COLUMNS. Once few enough cells are left for the exact solver,
+// each move also gets its proven result and how many plies it takes, counting the move itself:
+//
+//     move  visits  chance  proven
+//     4      51234   70.6%  win in 7 plies
+//     3       2011   40.2%  loss in 4 plies
+//
+// before that, only the results the search itself proved are shown, marked (search) and
+// without a distance. A search that proves the position stops there, and says so first:
+//
+//     proven: win in 9 plies
+use crate::{
+    board::{first_to_move, is_over, is_winning, play_column, show_grid, STATUS},
+    get_profile, init_graph, mcst, move_name, proof_distance, proof_text, restricted_graph, scale,
+    solver::{self, SolverCache},
+    suite,
+    theme::Theme,
+    tt, usage_error, Limits, Rules, TIME_PER_MOVE,
+};
+
+// the proven result of playing into `state`, for the player who did
+fn proven(state: (u64, u64), cache: &mut SolverCache) -> String {
+    let (q1, q2) = state;
+    if is_winning(q2) {
+        return "win in 1 ply".to_string();
+    }
+    let empty = 42 - (q1 | q2).count_ones() as i32;
+    let score = -solver::solve(q1, q2, cache);
+    // the score is the number of empty cells left after the winning move, plus one
+    let plies = empty - score.abs() + 2;
+    match score {
+        0 => "draw".to_string(),
+        score if score > 0 => format!("win in {plies} plies"),
+        _ => format!("loss in {plies} plies"),
+    }
+}
+
+// the value of the option `name`, taken out of the arguments
+fn take_columns(args: &mut Vec<String>, name: &str) -> Option<String> {
+    let i = args.iter().position(|arg| arg == name)?;
+    let columns = args
+        .get(i + 1)
+        .cloned()
+        .unwrap_or_else(|| usage_error(&format!("{name} needs columns, as in 345")));
+    args.drain(i..i + 2);
+    Some(columns)
+}
+
+// the states after playing each of the columns, without repeats
+fn play_columns(state: (u64, u64), columns: &str) -> Vec<(u64, u64)> {
+    let mut moves = vec![];
+    for c in columns.chars() {
+        let next = c
+            .to_digit(10)
+            .and_then(|column| play_column(state.0, state.1, column))
+            .unwrap_or_else(|| usage_error(&format!("illegal move: {c}")));
+        if !moves.contains(&next) {
+            moves.push(next);
+        }
+    }
+    moves
+}
+
+pub fn main(args: &[String]) {
+    let mut args = args.to_vec();
+    let searchmoves = take_columns(&mut args, "--searchmoves");
+    let excluded = take_columns(&mut args, "--exclude");
+    if searchmoves.is_some() && excluded.is_some() {
+        usage_error("--searchmoves and --exclude can't be combined");
+    }
+    let moves = args.first().map_or("", String::as_str);
+    let columns: Option<Vec<u32>> = moves.chars().map(|c| c.to_digit(10)).collect();
+    let (state, first) = columns
+        .and_then(|columns| Some((suite::replay(&columns)?, first_to_move(columns.len()))))
+        .unwrap_or_else(|| usage_error(&format!("invalid moves: {moves}")));
+    let time = match args.get(1) {
+        Some(time) => time
+            .parse()
+            .unwrap_or_else(|_| usage_error("the time must be a number")),
+        None => TIME_PER_MOVE,
+    };
+    show_grid(state.0, state.1, &Theme::plain(), first);
+    if is_over(state.0, state.1) {
+        println!("the game is over");
+        return;
+    }
+    let params = get_profile("default").unwrap();
+    let mut cache = SolverCache::new(tt::DEFAULT_MB);
+    let rules = Rules { swap: false };
+    let graph = match searchmoves {
+        Some(columns) => {
+            let moves = play_columns(state, &columns);
+            restricted_graph(state, first, false, moves, rules, &params, &mut cache)
+        }
+        None => init_graph(state, first),
+    };
+    let excluded = excluded.map_or(vec![], |columns| play_columns(state, &columns));
+    let (score, _, graph, _) = mcst(
+        graph,
+        0,
+        &Limits::movetime(time).excluding(excluded),
+        rules,
+        &params,
+        &mut cache,
+    );
+    println!(
+        "evaluation: {}, {} visits",
+        scale::percent(score),
+        graph[0].nb_visit
+    );
+    if let Some(plies) = proof_distance(&graph, 0, &params, &mut cache) {
+        println!("proven: {}", proof_text(&graph[0].status, plies));
+    }
+    let empty = 42 - (state.0 | state.1).count_ones();
+    let exact = empty <= params.solver_empty;
+    if !exact {
+        println!(
+            "{empty} empty cells, the exact solver takes over from {}",
+            params.solver_empty
+        );
+    }
+    println!("move  visits  chance  proven");
+    let mut children: Vec<usize> = graph[0].children().collect();
+    children.sort_by_key(|child| std::cmp::Reverse(graph[*child].nb_visit));
+    for child in children {
+        let node = &graph[child];
+        let result = match node.status {
+            _ if exact => proven(node.state, &mut cache),
+            // the status is for the player to move after the move
+            STATUS::WON => "loss (search)".to_string(),
+            STATUS::LOST => "win (search)".to_string(),
+            STATUS::DRAW => "draw (search)".to_string(),
+            STATUS::PLAYING => String::new(),
+        };
+        let line = format!(
+            "{:<4}{:>8}{:>8}  {result}",
+            move_name(state, node.state),
+            node.nb_visit,
+            scale::percent(node.mean())
+        );
+        println!("{}", line.trim_end());
+    }
+}
@@ -0,0 +1,139 @@
+// annotate FILE [GAME] [MS]: searches every position of a recorded game again and marks the
+// moves that gave away too much of the evaluation, for both players:
+//
+//     ?!  inaccuracy, 7.5% or more of the chances lost
+//     ?   mistake, 15% or more
+//     ??  blunder, 30% or more
+//
+// the marks go at the start of the move's comment, with the move the search preferred, and
+// the annotated game is printed in the record format.
+use crate::{
+    board::{first_to_move, get_status, STATUS},
+    get_profile, init_graph, mcst, move_name,
+    record::{self, Record},
+    rules, scale,
+    solver::SolverCache,
+    tt, usage_error, Limits, Params,
+};
+
+pub const ANALYSIS_TIME: u128 = 200; // milliseconds
+
+pub type Analysis = (Vec<(u64, u64)>, Vec<(f64, Option<(u64, u64)>)>);
+
+// threshold, symbol, name and plural
+pub const MARKS: [(f64, &str, &str, &str); 3] = [
+    (0.6, "??", "blunder", "blunders"),
+    (0.3, "?", "mistake", "mistakes"),
+    (0.15, "?!", "inaccuracy", "inaccuracies"),
+];
+
+// the positions of the game, and for each the best evaluation for the player to move with the
+// move getting it
+pub fn analyse(
+    game: &Record,
+    time: u128,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Result<Analysis, String> {
+    let states = game.replay()?;
+    // the searches and the statuses go by the game's rules
+    rules::set(game.variant()?);
+    let rules = game.rules();
+    let mut analysis = vec![];
+    for (ply, state) in states.iter().enumerate() {
+        let first = first_to_move(ply);
+        let value = match get_status(state.0, state.1, first) {
+            STATUS::PLAYING => {
+                let mut graph = init_graph(*state, first);
+                graph[0].can_swap = rules.swap && ply == 1;
+                let limits = Limits::movetime(time);
+                let (score, best, _, _) = mcst(graph, 0, &limits, rules, params, cache);
+                (score, Some(best))
+            }
+            STATUS::WON => (2.0, None),
+            STATUS::LOST => (0.0, None),
+            STATUS::DRAW => (1.0, None),
+        };
+        analysis.push(value);
+    }
+    Ok((states, analysis))
+}
+
+// the annotated game and how many moves got each mark, by player
+pub fn annotate(
+    game: &Record,
+    time: u128,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Result<(Record, [[u32; 3]; 2]), String> {
+    let (states, analysis) = analyse(game, time, params, cache)?;
+    let mut annotated = Record {
+        tags: game.tags.clone(),
+        moves: vec![],
+    };
+    let mut counts = [[0; 3]; 2];
+    for (ply, (text, comment)) in game.moves.iter().enumerate() {
+        let (best, best_state) = analysis[ply];
+        let played = 2.0 - analysis[ply + 1].0;
+        let mark = MARKS
+            .iter()
+            .position(|(threshold, _, _, _)| best - played >= *threshold);
+        let comment = match (mark, best_state) {
+            (Some(i), Some(best_state)) => {
+                counts[ply % 2][i] += 1;
+                let (_, symbol, kind, _) = MARKS[i];
+                Some(format!(
+                    "{symbol} {kind}, {} where {} kept {}",
+                    scale::percent(played),
+                    move_name(states[ply], best_state),
+                    scale::percent(best)
+                ))
+            }
+            _ => comment.clone(),
+        };
+        annotated.moves.push((text.clone(), comment));
+    }
+    Ok((annotated, counts))
+}
+
+pub fn print_summary(game: &Record, counts: &[[u32; 3]; 2]) {
+    for (player, tag) in ["First", "Second"].iter().enumerate() {
+        let marks: Vec<String> = MARKS
+            .iter()
+            .zip(counts[player])
+            .map(|((_, _, kind, kinds), count)| match count {
+                1 => format!("1 {kind}"),
+                _ => format!("{count} {kinds}"),
+            })
+            .collect();
+        println!("{}: {}", game.tag(tag).unwrap_or(tag), marks.join(", "));
+    }
+}
+
+pub fn main(args: &[String]) {
+    let path = args
+        .first()
+        .unwrap_or_else(|| usage_error("annotate needs a file"));
+    let records = record::load(path).unwrap_or_else(|error| usage_error(&error));
+    let parse = |i: usize, default: u128| match args.get(i) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| usage_error("annotate expects numbers")),
+        None => default,
+    };
+    let game = parse(1, 1) as usize;
+    let time = parse(2, ANALYSIS_TIME);
+    let game = records
+        .get(game.wrapping_sub(1))
+        .unwrap_or_else(|| usage_error(&format!("no game {game} in {path}")));
+    let params = Params {
+        contempt: 0.0,
+        temperature: 0.0,
+        ..get_profile("default").unwrap()
+    };
+    let mut cache = SolverCache::new(tt::DEFAULT_MB);
+    let (annotated, counts) =
+        annotate(game, time, &params, &mut cache).unwrap_or_else(|error| usage_error(&error));
+    print!("{}", annotated.format());
+    print_summary(&annotated, &counts);
+}
@@ -0,0 +1,76 @@
+// random playouts run by batches: all the games of a batch move together, one ply at a time,
+// over plain arrays of bitboards so the loops stay short and mostly free of branches
+use crate::{board::FULL_GRID, random, Params};
+
+pub const BATCH: usize = 16;
+
+const BOTTOM_ROW: u64 = 0b1111111;
+
+// the empty cells with something (or the floor) right below them
+fn playable(grid: u64) -> u64 {
+    ((grid << 8) | BOTTOM_ROW) & !grid & FULL_GRID
+}
+
+// the same test as board::is_winning, without returning early
+fn winning(player: u64) -> bool {
+    let lines = (player & player >> 1 & player >> 2 & player >> 3)
+        | (player & player >> 8 & player >> 16 & player >> 24)
+        | (player & player >> 9 & player >> 18 & player >> 27)
+        | (player & player >> 7 & player >> 14 & player >> 21);
+    lines != 0
+}
+
+// xorshift, much cheaper than the shared generator it's seeded from
+fn next(seed: &mut u64) -> u64 {
+    *seed ^= *seed << 13;
+    *seed ^= *seed >> 7;
+    *seed ^= *seed << 17;
+    *seed
+}
+
+// one of the set bits of the mask, at random
+fn pick_bit(mask: u64, seed: &mut u64) -> u64 {
+    let count = mask.count_ones() as u64;
+    let mut k = ((next(seed) & 0xffffffff) * count) >> 32;
+    let mut mask = mask;
+    while k > 0 {
+        mask &= mask - 1;
+        k -= 1;
+    }
+    mask & mask.wrapping_neg()
+}
+
+// plays BATCH random games out from the position, the total is for the player who just
+// moved (p2), `draw` being what a draw is worth to them
+pub fn simulation(p1: u64, p2: u64, draw: f64, params: &Params) -> f64 {
+    let mut players = [[p1; BATCH], [p2; BATCH]];
+    let mut results = [0.0; BATCH];
+    let mut running = [true; BATCH];
+    // xorshift can't start from 0
+    let mut seed = random::bits() | 1;
+    let mut plies = 0;
+    // the side to move is players[plies % 2] in every game at once
+    while running.contains(&true) {
+        let mover = plies % 2;
+        for lane in 0..BATCH {
+            if !running[lane] {
+                continue;
+            }
+            let grid = players[0][lane] | players[1][lane];
+            if grid == FULL_GRID {
+                results[lane] = draw;
+                running[lane] = false;
+                continue;
+            }
+            players[mover][lane] |= pick_bit(playable(grid), &mut seed);
+            if winning(players[mover][lane]) {
+                let win = 2.0 - params.shaping * (plies + 1) as f64 / 42.0;
+                // players[1] holds p2's discs
+                results[lane] = if mover == 1 { win } else { 2.0 - win };
+                running[lane] = false;
+            }
+        }
+        plies += 1;
+    }
+    results.iter().sum()
+}
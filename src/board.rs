@@ -0,0 +1,568 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    rules::{self, Scoring},
+    theme::Theme,
+};
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, PartialEq)]
+pub enum STATUS {
+    PLAYING,
+    WON,
+    LOST,
+    DRAW,
+}
+
+pub const FULL_GRID: u64 = 0b11111110111111101111111011111110111111101111111;
+
+// what the discs are drawn with, by seat rather than by whose turn it is
+#[derive(Clone, Copy, PartialEq)]
+pub struct Symbols {
+    pub first: char,
+    pub second: char,
+}
+
+pub const SYMBOLS: Symbols = Symbols {
+    first: 'X',
+    second: 'O',
+};
+
+impl Symbols {
+    // "XO" for the first player's symbol then the second one's
+    pub fn parse(text: &str) -> Option<Symbols> {
+        match text.chars().collect::<Vec<char>>()[..] {
+            [first, second]
+                if first != second && ![first, second].iter().any(|c| "#_ ".contains(*c)) =>
+            {
+                Some(Symbols { first, second })
+            }
+            _ => None,
+        }
+    }
+
+    // p1's and p2's symbols, p1 being the player to move
+    pub fn of(&self, first_to_move: bool) -> (char, char) {
+        if first_to_move {
+            (self.first, self.second)
+        } else {
+            (self.second, self.first)
+        }
+    }
+}
+
+impl std::fmt::Display for Symbols {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}{}", self.first, self.second)
+    }
+}
+
+// whether the player to move is the first one, `plies` moves into the game: the swap counts
+// as one and --extra-disc's disc doesn't, so the discs on the board can't tell
+pub fn first_to_move(plies: usize) -> bool {
+    plies.is_multiple_of(2)
+}
+
+// a four in a row is drawn with the theme's four whoever's it is
+pub fn show_grid(p1: u64, p2: u64, theme: &Theme, first_to_move: bool) {
+    let highlight = winning_cells(p1) | winning_cells(p2);
+    let (mine, theirs) = theme.symbols.of(first_to_move);
+    // the seats of p1 and p2
+    let (seat1, seat2) = if first_to_move { (0, 1) } else { (1, 0) };
+    for y in (0..6).rev() {
+        let mut line = String::new();
+        for x in 0..7 {
+            let i = y * 8 + x;
+            let glyph = |glyph| {
+                if 1 << i & highlight != 0 {
+                    theme.four
+                } else {
+                    glyph
+                }
+            };
+            line += &if 1 << i & p1 != 0 {
+                theme.paint(seat1, glyph(mine))
+            } else if 1 << i & p2 != 0 {
+                theme.paint(seat2, glyph(theirs))
+            } else {
+                theme.empty.to_string()
+            };
+        }
+        println!("{line}");
+    }
+}
+
+pub fn get_moves(p1: u64, p2: u64) -> Vec<(u64, u64)> {
+    let grid = p1 | p2;
+    let mut moves: Vec<(u64, u64)> = vec![];
+    for x in 0..7 {
+        for y in 0..6 {
+            let i = y * 8 + x;
+            if 1 << i & grid == 0 {
+                moves.push((p2, p1 | 1 << i));
+                break;
+            }
+        }
+    }
+    moves
+}
+
+// drops a disc for p1 in the column (1 -> 7), None if it's full or doesn't exist
+pub fn play_column(p1: u64, p2: u64, column: u32) -> Option<(u64, u64)> {
+    if !(1..=7).contains(&column) {
+        return None;
+    }
+    for y in 0..6 {
+        let i = y * 8 + column - 1;
+        if 1 << i & (p1 | p2) == 0 {
+            return Some((p2, p1 | 1 << i));
+        }
+    }
+    None
+}
+
+// trimmed and lowercased, the full-width forms input methods type turned to ascii
+fn normalize(text: &str) -> String {
+    text.trim()
+        .chars()
+        .map(|c| match c as u32 {
+            0xff01..=0xff5e => char::from_u32(c as u32 - 0xfee0).unwrap(),
+            0x3000 => ' ',
+            _ => c,
+        })
+        .collect::<String>()
+        .trim()
+        .to_lowercase()
+}
+
+// a column as it may be typed: 1 -> 7 (0 -> 6 when zero_based) or a -> g
+pub fn parse_column(text: &str, zero_based: bool) -> Option<u32> {
+    let text = normalize(text);
+    let mut chars = text.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+    let column = match c {
+        'a'..='g' => c as u32 - 'a' as u32 + 1,
+        '0'..='9' => c.to_digit(10)? + zero_based as u32,
+        _ => return None,
+    };
+    (1..=7).contains(&column).then_some(column)
+}
+
+// the columns (1 -> 7) with room left
+pub fn legal_columns(p1: u64, p2: u64) -> Vec<u32> {
+    let board = Board::new(p1, p2);
+    (1..=7).filter(|column| board.has_room(*column)).collect()
+}
+
+// why a typed or sent move can't be played
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MoveError {
+    // neither a column nor "s"
+    Unreadable,
+    // "s" after any other move than the first one, or without the swap rule
+    NoSwap,
+    // the column, 1 -> 7, has no room left
+    ColumnFull(u32),
+}
+
+impl std::fmt::Display for MoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MoveError::Unreadable => write!(f, "not a column"),
+            MoveError::NoSwap => write!(f, "no swap here"),
+            MoveError::ColumnFull(column) => write!(f, "column {column} is full"),
+        }
+    }
+}
+
+// a move as typed or sent: a column (see parse_column), or "s" for the swap
+pub fn parse_typed_move(
+    state: (u64, u64),
+    can_swap: bool,
+    text: &str,
+    zero_based: bool,
+) -> Result<(u64, u64), MoveError> {
+    if normalize(text) == "s" {
+        return can_swap.then_some(state).ok_or(MoveError::NoSwap);
+    }
+    let column = parse_column(text, zero_based).ok_or(MoveError::Unreadable)?;
+    if !Move::is_legal(column, &Board::new(state.0, state.1)) {
+        return Err(MoveError::ColumnFull(column));
+    }
+    Ok(play_column(state.0, state.1, column).unwrap())
+}
+
+pub fn parse_move(state: (u64, u64), can_swap: bool, text: &str) -> Result<(u64, u64), MoveError> {
+    parse_typed_move(state, can_swap, text, false)
+}
+
+pub fn is_winning(player: u64) -> bool {
+    // horizontal
+    if player & player >> 1 & player >> 2 & player >> 3 != 0 {
+        return true;
+    }
+    // vertical
+    if player & player >> 8 & player >> 16 & player >> 24 != 0 {
+        return true;
+    }
+    // top left -> bottom right
+    if player & player >> 9 & player >> 18 & player >> 27 != 0 {
+        return true;
+    }
+    // top right -> bottom left
+    if player & player >> 7 & player >> 14 & player >> 21 != 0 {
+        return true;
+    }
+    false
+}
+
+// the cells of every four in a row of the player, is_winning without returning early
+pub fn winning_cells(player: u64) -> u64 {
+    let mut cells = 0;
+    for step in [1, 8, 9, 7] {
+        let starts = player & player >> step & player >> (2 * step) & player >> (3 * step);
+        cells |= starts | starts << step | starts << (2 * step) | starts << (3 * step);
+    }
+    cells
+}
+
+// whether the game is over under the process' rules::Variant, whoever's turn it is
+pub fn is_over(p1: u64, p2: u64) -> bool {
+    rules::current().is_over(p1, p2)
+}
+
+// under the process' rules::Variant, for the player to move (p1), `first` when they are the
+// first player (see first_to_move)
+pub fn get_status(p1: u64, p2: u64, first: bool) -> STATUS {
+    rules::current().status(p1, p2, first)
+}
+
+const fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+// one random key per colour and cell, the same on every run
+const ZOBRIST: [[u64; 48]; 2] = {
+    let mut keys = [[0; 48]; 2];
+    let mut i = 0;
+    while i < 96 {
+        keys[i / 48][i % 48] = splitmix64(i as u64);
+        i += 1;
+    }
+    keys
+};
+
+// Zobrist hash of a position. The colour of a disc is told apart by the parity of the
+// disc count, so that p1 and p2 trading places after each move doesn't change the keys
+// of the discs already on the board.
+pub fn hash_state(p1: u64, p2: u64) -> u64 {
+    let nb_discs = (p1 | p2).count_ones() as usize;
+    let mut hash = 0;
+    for (i, (p1_key, p2_key)) in ZOBRIST[nb_discs % 2]
+        .iter()
+        .zip(&ZOBRIST[(nb_discs + 1) % 2])
+        .enumerate()
+    {
+        if 1 << i & p1 != 0 {
+            hash ^= p1_key;
+        } else if 1 << i & p2 != 0 {
+            hash ^= p2_key;
+        }
+    }
+    hash
+}
+
+// a position with its hash, kept up to date move after move
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Board {
+    // the player to move, then the one who just moved
+    pub p1: u64,
+    pub p2: u64,
+    hash: u64,
+}
+
+impl Board {
+    pub fn new(p1: u64, p2: u64) -> Board {
+        Board {
+            p1,
+            p2,
+            hash: hash_state(p1, p2),
+        }
+    }
+
+    pub fn state(&self) -> (u64, u64) {
+        (self.p1, self.p2)
+    }
+
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    // the board after a move from get_moves(), or after the swap when the state doesn't change
+    pub fn after(&self, state: (u64, u64)) -> Board {
+        if state == self.state() {
+            return *self;
+        }
+        let disc = state.1 ^ self.p1;
+        let nb_discs = (self.p1 | self.p2).count_ones() as usize;
+        Board {
+            p1: state.0,
+            p2: state.1,
+            hash: self.hash ^ ZOBRIST[nb_discs % 2][disc.trailing_zeros() as usize],
+        }
+    }
+
+    // whether the column (1 -> 7) exists and has room left
+    pub fn has_room(&self, column: u32) -> bool {
+        Move::is_legal(column, self)
+    }
+
+    // the legal moves, from column 1 to 7, the swap aside
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        (1..=7).filter_map(move |column| {
+            let board = self.play(column)?;
+            let cell = board.p2 ^ self.p1;
+            Some(Move {
+                column,
+                cell,
+                board,
+                creates_win: is_winning(board.p2),
+                blocks_loss: is_winning(self.p2 | cell),
+            })
+        })
+    }
+
+    // drops a disc in the column (1 -> 7), None if it's full or doesn't exist
+    pub fn play(&self, column: u32) -> Option<Board> {
+        play_column(self.p1, self.p2, column).map(|state| self.after(state))
+    }
+
+    // whether a game from the empty board can get there, under the process' rules
+    pub fn validate(&self) -> Result<(), InvalidBoard> {
+        let grid = self.p1 | self.p2;
+        if grid & !FULL_GRID != 0 {
+            return Err(InvalidBoard::OffGrid);
+        }
+        if self.p1 & self.p2 != 0 {
+            return Err(InvalidBoard::Overlap);
+        }
+        // the cells above the bottom row need one below them
+        if (grid >> 8) & !grid & FULL_GRID != 0 {
+            return Err(InvalidBoard::Floating);
+        }
+        let (to_move, moved) = (self.p1.count_ones(), self.p2.count_ones());
+        // the swap hands the first disc over without any new one
+        if moved != to_move && moved != to_move + 1 {
+            return Err(InvalidBoard::DiscCount);
+        }
+        if rules::current().scoring == Scoring::FirstFour && is_winning(self.p1) {
+            return Err(InvalidBoard::PlayedOn);
+        }
+        Ok(())
+    }
+}
+
+// what makes a position impossible
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InvalidBoard {
+    // a disc outside of the 7 x 6 grid
+    OffGrid,
+    // a cell with a disc of both players
+    Overlap,
+    // a disc with an empty cell below it
+    Floating,
+    // the player who just moved has neither as many discs as the other nor one more
+    DiscCount,
+    // the player to move already has a four
+    PlayedOn,
+}
+
+impl std::fmt::Display for InvalidBoard {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let reason = match self {
+            InvalidBoard::OffGrid => "a disc off the grid",
+            InvalidBoard::Overlap => "a cell taken by both players",
+            InvalidBoard::Floating => "a disc above an empty cell",
+            InvalidBoard::DiscCount => "wrong disc counts",
+            InvalidBoard::PlayedOn => "the game went on after a four",
+        };
+        write!(f, "{reason}")
+    }
+}
+
+// a disc dropped in a column by the player to move, with what it does, for whatever looks at
+// the moves of a position (the built-in players, explain); the playouts keep to get_moves,
+// which has none of it to work out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Move {
+    // 1 -> 7
+    pub column: u32,
+    // the cell the disc lands on
+    pub cell: u64,
+    // the opponent to move
+    pub board: Board,
+    // the disc makes a four
+    pub creates_win: bool,
+    // the disc takes a cell the opponent would make a four with
+    pub blocks_loss: bool,
+}
+
+impl Move {
+    // whether a disc can go in the column on the board
+    pub fn is_legal(column: u32, board: &Board) -> bool {
+        // its top cell
+        (1..=7).contains(&column) && 1 << (40 + column - 1) & (board.p1 | board.p2) == 0
+    }
+}
+
+// --strict: the positions the engine is given rather than played to (by the protocol's
+// `position board` or tree dumps) are checked with Board::validate before anything uses them;
+// the moves are always checked
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+// Ok when not strict
+pub fn check_position(state: (u64, u64)) -> Result<(), String> {
+    if !STRICT.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+    Board::new(state.0, state.1)
+        .validate()
+        .map_err(|error| format!("impossible position, {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the bottom row's first four cells
+    const FOUR: u64 = 0b1111;
+
+    #[test]
+    fn reachable_positions_are_valid() {
+        let mut state = (0, 0);
+        for column in [4, 4, 3, 5, 2, 2, 7] {
+            state = play_column(state.0, state.1, column).unwrap();
+            assert_eq!(Board::new(state.0, state.1).validate(), Ok(()));
+        }
+    }
+
+    #[test]
+    fn overlapping_bitboards() {
+        assert_eq!(Board::new(1, 1).validate(), Err(InvalidBoard::Overlap));
+        assert_eq!(Board::new(0, 1 << 7).validate(), Err(InvalidBoard::OffGrid));
+    }
+
+    #[test]
+    fn wrong_disc_counts() {
+        // the player to move ahead, then the other one two discs ahead
+        assert_eq!(
+            Board::new(0b111, 0).validate(),
+            Err(InvalidBoard::DiscCount)
+        );
+        assert_eq!(Board::new(0, 0b11).validate(), Err(InvalidBoard::DiscCount));
+        // the swap leaves the only disc with the player who didn't move
+        assert_eq!(Board::new(0, 1).validate(), Ok(()));
+    }
+
+    #[test]
+    fn floating_discs() {
+        assert_eq!(
+            Board::new(0, 1 << 8).validate(),
+            Err(InvalidBoard::Floating)
+        );
+        assert_eq!(
+            Board::new(1, 1 << 16).validate(),
+            Err(InvalidBoard::Floating)
+        );
+    }
+
+    #[test]
+    fn both_players_with_a_four() {
+        assert_eq!(
+            Board::new(FOUR, FOUR << 8).validate(),
+            Err(InvalidBoard::PlayedOn)
+        );
+        // only the player who just moved has one, the game is just over
+        let p1 = 0b111 << 8 | 1 << 4;
+        assert_eq!(Board::new(p1, FOUR).validate(), Ok(()));
+    }
+
+    #[test]
+    fn incremental_hash_matches_recomputed() {
+        crate::random::seed(1);
+        for _ in 0..200 {
+            let mut board = Board::new(0, 0);
+            let mut plies = 0;
+            while !is_over(board.p1, board.p2) {
+                let moves = get_moves(board.p1, board.p2);
+                // the swap, now and then, on the first move
+                let state = if plies == 1 && crate::random::below(2) == 0 {
+                    board.state()
+                } else {
+                    moves[crate::random::below(moves.len())]
+                };
+                board = board.after(state);
+                assert_eq!(board.hash(), hash_state(board.p1, board.p2));
+                plies += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn full_columns_are_not_legal() {
+        let mut board = Board::new(0, 0);
+        for _ in 0..6 {
+            assert!(Move::is_legal(1, &board));
+            board = board.play(1).unwrap();
+        }
+        assert!(!Move::is_legal(1, &board));
+        assert!(!Move::is_legal(0, &board) && !Move::is_legal(8, &board));
+    }
+
+    #[test]
+    fn columns_as_typed() {
+        assert_eq!(parse_column("4", false), Some(4));
+        assert_eq!(parse_column("c", false), Some(3));
+        assert_eq!(parse_column("G", false), Some(7));
+        assert_eq!(parse_column("0", true), Some(1));
+        assert_eq!(parse_column("6", true), Some(7));
+    }
+
+    #[test]
+    fn full_width_columns() {
+        // the digits and letters of input methods, and their space
+        assert_eq!(parse_column("\u{ff14}", false), Some(4));
+        assert_eq!(parse_column("\u{ff43}", false), Some(3));
+        assert_eq!(parse_column("\u{ff27}", false), Some(7));
+        assert_eq!(parse_column("\u{3000}\u{ff11}\u{3000}", false), Some(1));
+    }
+
+    #[test]
+    fn columns_with_whitespace() {
+        assert_eq!(parse_column("  5\n", false), Some(5));
+        assert_eq!(parse_column("\tb ", false), Some(2));
+        assert_eq!(parse_column("", false), None);
+        assert_eq!(parse_column("   ", false), None);
+        assert_eq!(parse_column("4 5", false), None);
+    }
+
+    #[test]
+    fn columns_out_of_range() {
+        assert_eq!(parse_column("0", false), None);
+        assert_eq!(parse_column("8", false), None);
+        assert_eq!(parse_column("7", true), None);
+        assert_eq!(parse_column("h", false), None);
+        assert_eq!(parse_column("\u{ff18}", false), None);
+        assert_eq!(parse_column("10", false), None);
+        assert_eq!(parse_column("-1", true), None);
+    }
+}
@@ -0,0 +1,214 @@
+// Opening book learnt from the games played (--book FILE, and the match option of the same
+// name for self-play), a chunks.rs file whose BOOK chunk has an entry per move of the positions
+// of the first PLIES plies:
+//
+//     <entries: u32>  then  <moves: string>  <move: string>  <visits: u64>  <mean: f64>
+//
+// moves being the ones played from the empty board (`4453`, "-" for none) and the mean what
+// the searches found of the move, from 0 to 2 for the player making it. Older builds wrote
+// tab separated text files, a line an entry, which still load and are written the new way
+// after the next game. After each game the new visits of its searches are merged in, the means
+// weighted by the visits, and a search of a position the tree knows nothing of starts from the
+// book's statistics, WEIGHT visits' worth at most.
+use std::{collections::BTreeMap, fs, io::ErrorKind};
+
+use crate::{
+    chunks::{self, push_string, Reader, Tag},
+    expansion, get_node_moves, move_name,
+    solver::SolverCache,
+    Node, Params, Rules,
+};
+
+pub const PLIES: usize = 8;
+const BOOK: Tag = *b"BOOK";
+const WEIGHT: f64 = 2000.0;
+
+// what the searches found of a move
+pub struct Stat {
+    pub position: String,
+    pub name: String,
+    pub visits: u64,
+    pub mean: f64,
+}
+
+#[derive(Clone, Default)]
+pub struct Book {
+    // by position then move, the visits and the mean
+    moves: BTreeMap<(String, String), (u64, f64)>,
+}
+
+pub fn key(moves: &[String]) -> String {
+    if moves.is_empty() {
+        return "-".to_string();
+    }
+    moves.concat()
+}
+
+impl Book {
+    pub fn merge(&mut self, stats: &[Stat]) {
+        for stat in stats.iter().filter(|stat| stat.visits > 0) {
+            let key = (stat.position.clone(), stat.name.clone());
+            let (visits, mean) = self.moves.entry(key).or_insert((0, 0.0));
+            let total = *visits + stat.visits;
+            *mean = (*visits as f64 * *mean + stat.visits as f64 * stat.mean) / total as f64;
+            *visits = total;
+        }
+    }
+
+    // gives the root's moves the book's statistics, unless the tree already searched it
+    pub fn prime(
+        &self,
+        position: &str,
+        graph: &mut Vec<Node>,
+        root: usize,
+        rules: Rules,
+        params: &Params,
+        cache: &mut SolverCache,
+    ) -> bool {
+        let known: Vec<(&str, u64, f64)> = self
+            .moves
+            .range((position.to_string(), String::new())..)
+            .take_while(|((entry, _), _)| entry == position)
+            .map(|((_, name), (visits, mean))| (name.as_str(), *visits, *mean))
+            .collect();
+        if graph[root].nb_visit > 0 || known.is_empty() {
+            return false;
+        }
+        if graph[root].nb_children == 0 {
+            let moves = get_node_moves(&graph[root]);
+            if moves.is_empty() {
+                return false;
+            }
+            expansion(root, graph, moves, rules, params, cache);
+        }
+        let total: u64 = known.iter().map(|(_, visits, _)| visits).sum();
+        let scale = (WEIGHT / total as f64).min(1.0);
+        let state = graph[root].state;
+        for child in graph[root].children() {
+            let name = move_name(state, graph[child].state);
+            let Some((_, visits, mean)) = known.iter().find(|(known, _, _)| *known == name) else {
+                continue;
+            };
+            let visits = ((*visits as f64 * scale).round() as u32).max(1);
+            graph[child].nb_visit += visits;
+            graph[child].score += (visits as f64 * mean) as f32;
+            // the root's score is for the other player
+            graph[root].nb_visit += visits;
+            graph[root].score += (visits as f64 * (2.0 - mean)) as f32;
+        }
+        true
+    }
+}
+
+// the visits and scores of the root's moves, to tell what a search added
+pub fn snapshot(graph: &[Node], root: usize) -> Vec<(u32, f32)> {
+    graph[root]
+        .children()
+        .map(|child| (graph[child].nb_visit, graph[child].score))
+        .collect()
+}
+
+// what the searches found of the root's moves since the snapshot `before`
+pub fn learnt(position: &str, graph: &[Node], root: usize, before: &[(u32, f32)]) -> Vec<Stat> {
+    let state = graph[root].state;
+    graph[root]
+        .children()
+        .enumerate()
+        .filter_map(|(i, child)| {
+            let (visits, score) = before.get(i).copied().unwrap_or((0, 0.0));
+            let node = &graph[child];
+            let new = node.nb_visit.checked_sub(visits).filter(|new| *new > 0)?;
+            Some(Stat {
+                position: position.to_string(),
+                name: move_name(state, node.state),
+                visits: new as u64,
+                mean: (node.score - score) as f64 / new as f64,
+            })
+        })
+        .collect()
+}
+
+// an older build's text book
+fn parse_text(path: &str, content: &str) -> Result<Book, String> {
+    let mut book = Book::default();
+    for (n, line) in content.lines().enumerate() {
+        let invalid = || format!("{path}:{}: invalid book line", n + 1);
+        match line.split('\t').collect::<Vec<&str>>()[..] {
+            [position, name, visits, mean] => {
+                let visits = visits.parse().map_err(|_| invalid())?;
+                let mean = mean.parse().map_err(|_| invalid())?;
+                book.moves
+                    .insert((position.to_string(), name.to_string()), (visits, mean));
+            }
+            [""] => {}
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(book)
+}
+
+fn read_book(path: &str, data: &[u8]) -> Result<Book, String> {
+    let mut reader = Reader::new(path, data);
+    let mut book = Book::default();
+    for _ in 0..reader.u32()? {
+        let key = (reader.string()?, reader.string()?);
+        let visits = reader.u64()?;
+        let mean = reader.f64()?;
+        book.moves.insert(key, (visits, mean));
+    }
+    Ok(book)
+}
+
+// a missing file is an empty book, one that can't be read an error, so the update after the
+// game doesn't overwrite it
+pub fn load(path: &str) -> Result<Book, String> {
+    let content = match fs::read(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Book::default()),
+        Err(error) => return Err(format!("can't read {path}: {error}")),
+    };
+    match chunks::read(path, &content, &[BOOK])? {
+        Some(chunks) => match chunks.iter().find(|(tag, _)| *tag == BOOK) {
+            Some((_, data)) => read_book(path, data),
+            None => Ok(Book::default()),
+        },
+        None => match String::from_utf8(content) {
+            Ok(content) => parse_text(path, &content),
+            Err(_) => Err(format!("{path} is not an opening book")),
+        },
+    }
+}
+
+pub fn save(path: &str, book: &Book) -> Result<(), String> {
+    let mut data = vec![];
+    data.extend((book.moves.len() as u32).to_le_bytes());
+    for ((position, name), (visits, mean)) in &book.moves {
+        push_string(&mut data, position);
+        push_string(&mut data, name);
+        data.extend(visits.to_le_bytes());
+        data.extend(mean.to_le_bytes());
+    }
+    chunks::write(path, &[(BOOK, data)])
+}
+
+// merges the statistics into the book file, returning how many moves it has then
+pub fn update(path: &str, stats: &[Stat]) -> Result<usize, String> {
+    let mut book = load(path)?;
+    book.merge(stats);
+    save(path, &book)?;
+    Ok(book.moves.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_missing_file_is_empty() {
+        let dir = std::env::temp_dir();
+        let missing = dir.join("mcst_connect4_no_such_file");
+        assert!(load(missing.to_str().unwrap()).unwrap().moves.is_empty());
+        // a directory can't be read
+        assert!(load(dir.to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,190 @@
+// The binary container of the files the engine keeps (tree dumps and opening books):
+//
+//     MAGIC  <version: u16>  <width: u8>  <height: u8>  <connect: u8>  <scoring: u8>
+//     <full board: u8>  then chunks of  <tag: 4 bytes>  <length: u32>  <data>
+//
+// numbers little-endian, the rules being the indices in rules::SCORINGS and FULL_BOARDS. A
+// reader skips the chunks it doesn't know when their tag starts with a lowercase letter
+// (extras a newer build may add) and rejects the file for the others, as it does a newer
+// version or another board. Files from before the container (the text formats) have no magic,
+// and their loaders read them the old way.
+use std::fs;
+
+use crate::rules::{self, Variant, FULL_BOARDS, SCORINGS};
+
+const MAGIC: &[u8; 8] = b"mcst\r\n\x1a\n";
+pub const VERSION: u16 = 1;
+const BOARD: (u8, u8, u8) = (7, 6, 4);
+
+pub type Tag = [u8; 4];
+// a tag and its data
+pub type Chunk = (Tag, Vec<u8>);
+
+fn variant_bytes(variant: Variant) -> [u8; 2] {
+    let scoring = SCORINGS.iter().position(|(_, x)| *x == variant.scoring);
+    let full_board = FULL_BOARDS
+        .iter()
+        .position(|(_, x)| *x == variant.full_board);
+    [scoring.unwrap() as u8, full_board.unwrap() as u8]
+}
+
+// a file of the chunks, for the board and rules of the process
+pub fn write(path: &str, chunks: &[Chunk]) -> Result<(), String> {
+    let mut content = MAGIC.to_vec();
+    content.extend(VERSION.to_le_bytes());
+    content.extend([BOARD.0, BOARD.1, BOARD.2]);
+    content.extend(variant_bytes(rules::current()));
+    for (tag, data) in chunks {
+        content.extend(tag);
+        content.extend((data.len() as u32).to_le_bytes());
+        content.extend(data);
+    }
+    fs::write(path, content).map_err(|error| format!("can't write {path}: {error}"))
+}
+
+// the chunks with the tags in `known`, None when the file predates the container
+pub fn read(path: &str, content: &[u8], known: &[Tag]) -> Result<Option<Vec<Chunk>>, String> {
+    let Some(rest) = content.strip_prefix(MAGIC) else {
+        return Ok(None);
+    };
+    let mut reader = Reader::new(path, rest);
+    let version = u16::from_le_bytes([reader.u8()?, reader.u8()?]);
+    if version > VERSION {
+        return Err(format!(
+            "{path} comes from a newer build (format {version}, this one reads up to {VERSION})"
+        ));
+    }
+    let board = (reader.u8()?, reader.u8()?, reader.u8()?);
+    if board != BOARD {
+        return Err(format!(
+            "{path} is for a {}x{} board with {} in a row",
+            board.0, board.1, board.2
+        ));
+    }
+    let variant = [reader.u8()?, reader.u8()?];
+    if variant != variant_bytes(rules::current()) {
+        return Err(format!("{path} is for other rules than these"));
+    }
+    let mut chunks = vec![];
+    while !reader.is_empty() {
+        let tag: Tag = reader.bytes(4)?.try_into().unwrap();
+        let length = reader.u32()? as usize;
+        let data = reader.bytes(length)?;
+        if known.contains(&tag) {
+            chunks.push((tag, data.to_vec()));
+        } else if !tag[0].is_ascii_lowercase() {
+            return Err(format!(
+                "{path} has a {} chunk this build doesn't know",
+                String::from_utf8_lossy(&tag)
+            ));
+        }
+    }
+    Ok(Some(chunks))
+}
+
+// reads the data of a chunk, a short one being an error about the file
+pub struct Reader<'a> {
+    path: &'a str,
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(path: &'a str, data: &'a [u8]) -> Reader<'a> {
+        Reader { path, data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        if self.data.len() < length {
+            return Err(format!("{} is truncated", self.path));
+        }
+        let (bytes, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn f64(&mut self) -> Result<f64, String> {
+        Ok(f64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    // a length byte then utf-8
+    pub fn string(&mut self) -> Result<String, String> {
+        let length = self.u8()? as usize;
+        String::from_utf8(self.bytes(length)?.to_vec())
+            .map_err(|_| format!("{} has an invalid string", self.path))
+    }
+}
+
+pub fn push_string(data: &mut Vec<u8>, text: &str) {
+    data.push(text.len() as u8);
+    data.extend(text.as_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("mcst-chunks-{}-{name}", std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = temp_path("round-trip");
+        let chunks = vec![(*b"TREE", vec![1, 2, 3]), (*b"BOOK", vec![])];
+        write(&path, &chunks).unwrap();
+        let content = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let read_back = read(&path, &content, &[*b"TREE", *b"BOOK"]).unwrap();
+        assert_eq!(read_back, Some(chunks));
+    }
+
+    #[test]
+    fn truncated() {
+        let path = temp_path("truncated");
+        write(&path, &[(*b"TREE", vec![1, 2, 3])]).unwrap();
+        let content = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let error = read(&path, &content[..content.len() - 1], &[*b"TREE"]).unwrap_err();
+        assert!(error.ends_with("is truncated"), "{error}");
+    }
+
+    #[test]
+    fn unknown_chunks() {
+        let path = temp_path("unknown");
+        write(&path, &[(*b"xtra", vec![9]), (*b"TREE", vec![1])]).unwrap();
+        let content = fs::read(&path).unwrap();
+        // a lowercase tag is skipped, an uppercase one rejects the file
+        let chunks = read(&path, &content, &[*b"TREE"]).unwrap();
+        assert_eq!(chunks, Some(vec![(*b"TREE", vec![1])]));
+        write(&path, &[(*b"XTRA", vec![9]), (*b"TREE", vec![1])]).unwrap();
+        let content = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(read(&path, &content, &[*b"TREE"]).is_err());
+    }
+
+    #[test]
+    fn text_files_predate_the_container() {
+        assert_eq!(read("old", b"mcst-tree 2\nroot 0\n", &[]), Ok(None));
+    }
+}
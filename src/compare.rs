@@ -0,0 +1,125 @@
+// compare-policies [ITERATIONS] [SEED]: the same fixed-iteration, fixed-seed searches on a set
+// of positions under every selection and playout policy, each chosen move checked against the
+// exact solver
+use std::time::Instant;
+
+use crate::{
+    board::{first_to_move, get_moves, is_winning},
+    get_profile, init_graph, mcst, random,
+    solver::{self, SolverCache},
+    suite, tt, usage_error, Limits, Params, Rules, PLAYOUTS, SELECTIONS,
+};
+
+const DEFAULT_ITERATIONS: u64 = 2000;
+const DEFAULT_SEED: u64 = 1;
+
+// middlegames from self-play, late enough for the solver to settle them quickly
+pub const POSITIONS: [&str; 10] = [
+    "7644634433166334176774",
+    "7644467767622674422241",
+    "6552333535536622426121",
+    "4744433334377775555413",
+    "26434423336446634636",
+    "1244224426666421164135",
+    "4744433334377737452711",
+    "634434344375357776",
+    "134433433475721775",
+    "17444411127577666",
+];
+
+// the moves keeping the best outcome the solver can prove
+fn solver_moves(state: (u64, u64), cache: &mut SolverCache) -> Vec<(u64, u64)> {
+    let moves = get_moves(state.0, state.1);
+    let outcomes: Vec<i32> = moves
+        .iter()
+        .map(|(q1, q2)| {
+            if is_winning(*q2) {
+                1
+            } else {
+                -solver::solve(*q1, *q2, cache).signum()
+            }
+        })
+        .collect();
+    let best = *outcomes.iter().max().unwrap();
+    moves
+        .into_iter()
+        .zip(outcomes)
+        .filter(|(_, outcome)| *outcome == best)
+        .map(|(state, _)| state)
+        .collect()
+}
+
+fn parse_arg(args: &[String], i: usize, default: u64, name: &str) -> u64 {
+    match args.get(i) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| usage_error(&format!("the {name} must be a number"))),
+        None => default,
+    }
+}
+
+pub fn main(args: &[String]) {
+    let iterations = parse_arg(args, 0, DEFAULT_ITERATIONS, "number of iterations");
+    let seed = parse_arg(args, 1, DEFAULT_SEED, "seed");
+    let rules = Rules { swap: false };
+    let mut cache = SolverCache::new(tt::DEFAULT_MB);
+    let positions: Vec<_> = POSITIONS
+        .iter()
+        .map(|moves| {
+            let columns: Vec<u32> = moves.chars().filter_map(|c| c.to_digit(10)).collect();
+            let state = suite::replay(&columns).unwrap();
+            let first = first_to_move(columns.len());
+            (state, first, solver_moves(state, &mut cache))
+        })
+        .collect();
+    println!(
+        "{} positions, {iterations} iterations each, seed {seed}",
+        positions.len()
+    );
+    println!(
+        "{:<16}{:>8}{:>10}{:>10}{:>12}",
+        "policy", "agree", "nodes", "nodes/ms", "playouts/ms"
+    );
+    for (selection_name, selection) in SELECTIONS {
+        for (playout_name, playout) in PLAYOUTS {
+            let params = Params {
+                selection,
+                playout,
+                max_iterations: Some(iterations),
+                // the solver would answer for the policies
+                solver_empty: 0,
+                ..get_profile("default").unwrap()
+            };
+            let mut agree = 0;
+            let mut nodes = 0;
+            let mut playouts = 0;
+            let now = Instant::now();
+            for (i, (state, first, best)) in positions.iter().enumerate() {
+                random::seed(seed + i as u64);
+                let (_, chosen, graph, _) = mcst(
+                    init_graph(*state, *first),
+                    0,
+                    &Limits::movetime(u128::MAX),
+                    rules,
+                    &params,
+                    &mut cache,
+                );
+                if best.contains(&chosen) {
+                    agree += 1;
+                }
+                nodes += graph.len();
+                playouts += graph[0].nb_visit as u64;
+            }
+            // whole milliseconds would round the short runs away
+            let ms = now.elapsed().as_secs_f64() * 1000.0;
+            println!(
+                "{:<16}{:>8}{:>10}{:>10.1}{:>12.1}",
+                format!("{selection_name}+{playout_name}"),
+                format!("{agree}/{}", positions.len()),
+                nodes / positions.len(),
+                nodes as f64 / ms,
+                playouts as f64 / ms
+            );
+        }
+    }
+}
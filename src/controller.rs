@@ -0,0 +1,643 @@
+// A game between a front-end's player and the engine, everything the main loop keeps track of
+// in one place: the position and the moves that led to it, the engine's tree, and how long each
+// player thought. Front-ends only say what happens (a move, the engine's turn, an undo) and
+// read the result.
+//
+// simple [OPTIONS] drives one from the standard input, one command per line:
+//
+//     COLUMN        the player's move, a column as typed in a game (or s for the swap)
+//     go            the engine's move, which also follows each of the player's moves
+//     undo          takes back the player's last move and the engine's reply, if it had one
+//     quit
+//
+// and answers `engine MOVE eval EVAL`, `result 1-0|0-1|1/2-1/2` once the game is over (with
+// each player's thinking time) or `error <message>`.
+//
+// Every game played through one, whichever the front-end, goes to the hooks (with the hooks
+// feature, see hooks.rs): its start with its first move, each move, and its end.
+use std::{
+    io::{self, BufRead},
+    mem,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use crate::{
+    advance_root,
+    board::{first_to_move, get_status, parse_column, play_column, MoveError, STATUS},
+    collect_garbage,
+    explain::principal_variation,
+    get_node_moves, init_graph, mcst, move_name, node_cap, random,
+    record::{self, Record},
+    search::{self, Position, SearchFuture, SearchResult},
+    solver::SolverCache,
+    timing::Curve,
+    Limits, Node, Options, Params, Rules, SearchStats, TIME_PER_MOVE,
+};
+#[cfg(feature = "hooks")]
+use crate::{hooks, scale};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameResult {
+    Playing,
+    // the first (0) or the second player (1)
+    Won(usize),
+    Drawn,
+}
+
+pub struct EngineMove {
+    pub name: String,
+    // from 0 to 2, for the engine
+    pub eval: f64,
+}
+
+// a move of the root and what the searches know of it, for whoever drives the engine
+pub struct ChildStats {
+    pub name: String,
+    pub visits: u32,
+    // from 0 to 2, for the player making the move
+    pub mean: f64,
+    // proven for the player making the move, PLAYING until then
+    pub status: STATUS,
+}
+
+pub struct GameController {
+    rules: Rules,
+    params: Params,
+    // milliseconds a move, before the curve's factor for the ply
+    time: u128,
+    curve: Curve,
+    cache: SolverCache,
+    graph: Vec<Node>,
+    root: usize,
+    // the positions from the start, the current one last
+    history: Vec<(u64, u64)>,
+    // the players, the moves with their comments and, once it's over, the result
+    record: Record,
+    // the first and the second player's thinking time, milliseconds
+    clocks: [u128; 2],
+    turn_start: Instant,
+    // the engine's searches, play_engine's and those given stats()
+    stats: Arc<Mutex<SearchStats>>,
+    // the engine's searches' workers (--threads)
+    threads: usize,
+    // the fast profile's cap on the tree, --max-memory megabytes with the table
+    max_memory_mb: Option<usize>,
+    // how it ended when it wasn't on the board: a forfeit or an adjudication
+    decided: Option<GameResult>,
+    // whether the hooks were told about the start
+    #[cfg(feature = "hooks")]
+    started: bool,
+}
+
+impl GameController {
+    pub fn new(rules: Rules, params: Params, time: u128, tt_mb: usize) -> GameController {
+        GameController {
+            rules,
+            params,
+            time,
+            curve: Curve::default(),
+            cache: SolverCache::new(tt_mb),
+            graph: init_graph((0, 0), true),
+            root: 0,
+            history: vec![(0, 0)],
+            // the front-end names the players
+            record: Record::new("?", "?", rules),
+            clocks: [0, 0],
+            turn_start: Instant::now(),
+            stats: Arc::default(),
+            threads: 1,
+            max_memory_mb: None,
+            decided: None,
+            #[cfg(feature = "hooks")]
+            started: false,
+        }
+    }
+
+    // a game from another position than the empty board, the tree given kept when it is the
+    // position's
+    pub(crate) fn starting_from(
+        mut self,
+        state: (u64, u64),
+        tree: Option<(Vec<Node>, usize)>,
+    ) -> GameController {
+        (self.graph, self.root) = match tree {
+            Some((graph, root)) if graph[root].state == state => (graph, root),
+            // the first player to move, whatever discs the position already has
+            _ => (init_graph(state, true), 0),
+        };
+        self.history = vec![state];
+        self
+    }
+
+    // the players' names and whatever tags the front-end has for the game
+    pub(crate) fn with_record(mut self, record: Record) -> GameController {
+        self.record = record;
+        self
+    }
+
+    pub(crate) fn with_cache(mut self, cache: SolverCache) -> GameController {
+        self.cache = cache;
+        self
+    }
+
+    // root-parallel searches for the engine's moves, see Limits::with_threads
+    pub fn with_threads(mut self, threads: usize) -> GameController {
+        self.threads = threads;
+        self
+    }
+
+    // the engine's time from one ply to the next, see timing.rs
+    pub(crate) fn with_time_curve(mut self, curve: Curve) -> GameController {
+        self.curve = curve;
+        self
+    }
+
+    // the engine's searches stop before the tree and the table take more than `max_memory_mb`
+    pub fn capped(mut self, max_memory_mb: usize) -> GameController {
+        self.max_memory_mb = Some(max_memory_mb);
+        self
+    }
+
+    // for the engine's next searches, as the front-end's settings changed
+    pub(crate) fn set_engine(&mut self, params: Params, time: u128) {
+        (self.params, self.time) = (params, time);
+    }
+
+    // milliseconds for the engine's search of the current position
+    pub fn move_time(&self) -> u128 {
+        self.curve
+            .time(self.time, self.record.moves.len() as u32 + 1)
+    }
+
+    pub fn state(&self) -> (u64, u64) {
+        *self.history.last().unwrap()
+    }
+
+    pub fn moves(&self) -> Vec<&str> {
+        self.record
+            .moves
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect()
+    }
+
+    pub fn record(&self) -> &Record {
+        &self.record
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    // the engine's, for its searches
+    pub fn params(&self) -> Params {
+        self.params
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        self.record.set_tag(name, value);
+    }
+
+    pub fn clocks(&self) -> [u128; 2] {
+        self.clocks
+    }
+
+    // 0 when it's the first player's turn, the swap counting as a move
+    pub fn to_move(&self) -> usize {
+        self.record.moves.len() % 2
+    }
+
+    pub fn can_swap(&self) -> bool {
+        self.graph[self.root].can_swap
+    }
+
+    // the current position's moves and what the engine's searches know of them so far
+    pub fn root_children(&self) -> impl Iterator<Item = ChildStats> + '_ {
+        root_children(&self.graph, self.root)
+    }
+
+    // the most visited line from the current position
+    pub fn pv(&self) -> Vec<String> {
+        principal_variation(&self.graph, self.root)
+    }
+
+    // the nodes below the current position, itself included
+    pub fn node_count(&self) -> usize {
+        node_count(&self.graph, self.root)
+    }
+
+    // what the engine's searches added up to so far, the counters they go to
+    pub(crate) fn stats(&self) -> Arc<Mutex<SearchStats>> {
+        self.stats.clone()
+    }
+
+    pub(crate) fn graph(&self) -> &[Node] {
+        &self.graph
+    }
+
+    pub(crate) fn root(&self) -> usize {
+        self.root
+    }
+
+    // for the front-end's own searches of the position, the root staying where it is
+    pub(crate) fn parts(&mut self) -> (&mut Vec<Node>, usize, &mut SolverCache) {
+        (&mut self.graph, self.root, &mut self.cache)
+    }
+
+    // drops what the moves played left out of reach
+    pub(crate) fn compact(&mut self) {
+        (self.graph, self.root) = collect_garbage(mem::take(&mut self.graph), self.root);
+    }
+
+    // the record, the tree and the table, once the game is over (or abandoned there)
+    pub(crate) fn finish(mut self) -> (Record, Vec<Node>, SolverCache) {
+        self.abandon();
+        let record = Record::new("?", "?", self.rules);
+        (
+            mem::replace(&mut self.record, record),
+            mem::take(&mut self.graph),
+            mem::replace(&mut self.cache, SolverCache::new(0)),
+        )
+    }
+
+    fn check_playing(&self) -> Result<(), String> {
+        match self.result() {
+            GameResult::Playing => Ok(()),
+            _ => Err("the game is over".to_string()),
+        }
+    }
+
+    fn apply(&mut self, state: (u64, u64), comment: Option<String>) {
+        #[cfg(feature = "hooks")]
+        self.announce_start();
+        let now = Instant::now();
+        self.clocks[self.to_move()] += now.duration_since(self.turn_start).as_millis();
+        self.turn_start = now;
+        self.record.push(&move_name(self.state(), state), comment);
+        self.history.push(state);
+        self.root = advance_root(&mut self.graph, self.root, state, self.rules);
+        #[cfg(feature = "hooks")]
+        {
+            let node = &self.graph[self.root];
+            hooks::emit(&hooks::Event::Move {
+                game: &self.record,
+                state,
+                // for the player who moved, once the tree knows something of the position
+                chances: (node.nb_visit > 0 || node.status != STATUS::PLAYING)
+                    .then(|| scale::probability(node.mean())),
+                visits: node.nb_visit,
+            });
+        }
+        if self.result() != GameResult::Playing {
+            self.game_over();
+        }
+    }
+
+    fn game_over(&mut self) {
+        let result = match self.result() {
+            GameResult::Won(seat) => record::result_text(Some(seat)),
+            GameResult::Drawn => record::result_text(None),
+            GameResult::Playing => "*",
+        };
+        self.record.set_tag("Result", result);
+        let stats = *self.stats.lock().unwrap();
+        if stats.searches > 0 {
+            self.record.set_tag("Engine", &stats.tag());
+        }
+        #[cfg(feature = "hooks")]
+        hooks::emit(&hooks::Event::End {
+            game: &self.record,
+            state: self.state(),
+            clocks: self.clocks,
+            stats: &stats,
+        });
+    }
+
+    // a game given up before its end, a front-end starting another or going away: the hooks
+    // hear of the end all the same, the result staying *
+    fn abandon(&mut self) {
+        if self.record.moves.is_empty() || self.result() != GameResult::Playing {
+            return;
+        }
+        self.record.set_tag("Termination", "abandoned");
+        self.game_over();
+    }
+
+    // once the front-end has named the players and tagged the game
+    #[cfg(feature = "hooks")]
+    fn announce_start(&mut self) {
+        if !mem::replace(&mut self.started, true) {
+            hooks::emit(&hooks::Event::Start {
+                game: &self.record,
+                state: self.history[0],
+            });
+        }
+    }
+
+    // any move of the position (the swap being the position unchanged), the comment going to
+    // the record
+    pub fn play(&mut self, state: (u64, u64), comment: Option<String>) -> Result<(), String> {
+        self.check_playing()?;
+        if !get_node_moves(&self.graph[self.root]).contains(&state) {
+            return Err(format!("no move to {state:x?}"));
+        }
+        self.apply(state, comment);
+        Ok(())
+    }
+
+    // a column from 1 to 7
+    pub fn play_human(&mut self, column: u32) -> Result<(), String> {
+        self.check_playing()?;
+        let (p1, p2) = self.state();
+        let state = match play_column(p1, p2, column) {
+            Some(state) => state,
+            None if (1..=7).contains(&column) => {
+                return Err(MoveError::ColumnFull(column).to_string())
+            }
+            None => return Err(MoveError::Unreadable.to_string()),
+        };
+        self.apply(state, None);
+        Ok(())
+    }
+
+    // the second player takes the first move over
+    pub fn swap(&mut self) -> Result<(), String> {
+        self.check_playing()?;
+        if !self.can_swap() {
+            return Err(MoveError::NoSwap.to_string());
+        }
+        self.apply(self.state(), None);
+        Ok(())
+    }
+
+    pub fn play_engine(&mut self) -> Result<EngineMove, String> {
+        self.play_engine_with(|limits| limits, None)
+    }
+
+    // play_engine with the front-end's say in the search: `adjust` changes its limits (to
+    // report on it, say) and `watch`, given it running in the background, waits for it
+    pub(crate) fn play_engine_with(
+        &mut self,
+        adjust: impl FnOnce(Limits) -> Limits,
+        watch: Option<&dyn Fn(SearchFuture) -> SearchResult>,
+    ) -> Result<EngineMove, String> {
+        self.check_playing()?;
+        let state = self.state();
+        let graph = mem::take(&mut self.graph);
+        let max_nodes = self
+            .max_memory_mb
+            .map(|max_memory_mb| node_cap(max_memory_mb, &self.cache));
+        let limits = adjust(
+            Limits::movetime(self.move_time())
+                .with_stats(self.stats.clone())
+                .with_threads(self.threads)
+                .capped(max_nodes),
+        );
+        let (eval, best);
+        if let Some(watch) = watch {
+            let position = Position {
+                graph,
+                root: self.root,
+                rules: self.rules,
+                params: self.params,
+                cache: mem::replace(&mut self.cache, SolverCache::new(0)),
+            };
+            let result = watch(search::search(position, limits));
+            (eval, best) = (result.score, result.best);
+            (self.graph, self.cache) = (result.position.graph, result.position.cache);
+        } else {
+            (eval, best, self.graph, _) = mcst(
+                graph,
+                self.root,
+                &limits,
+                self.rules,
+                &self.params,
+                &mut self.cache,
+            );
+        }
+        self.apply(best, Some(format!("eval {eval:.4}")));
+        Ok(EngineMove {
+            name: move_name(state, best),
+            eval,
+        })
+    }
+
+    // the player to move loses, the reason going to the record's Termination tag
+    pub fn forfeit(&mut self, termination: &str) -> Result<(), String> {
+        self.adjudicate(GameResult::Won(1 - self.to_move()), termination)
+    }
+
+    // ends the game with `result` whatever the board says, as forfeit does
+    pub fn adjudicate(&mut self, result: GameResult, termination: &str) -> Result<(), String> {
+        self.check_playing()?;
+        if result == GameResult::Playing {
+            return Err("an adjudication ends the game".to_string());
+        }
+        #[cfg(feature = "hooks")]
+        self.announce_start();
+        self.decided = Some(result);
+        self.record.set_tag("Termination", termination);
+        self.game_over();
+        Ok(())
+    }
+
+    // takes the last move back, with a new tree for the position before it
+    pub fn undo(&mut self) -> Result<(), String> {
+        if self.record.moves.is_empty() {
+            return Err("no move to take back".to_string());
+        }
+        self.record.moves.pop();
+        self.history.pop();
+        // the game goes on
+        self.decided = None;
+        self.record.set_tag("Result", "*");
+        self.record.tags.retain(|(name, _)| name != "Termination");
+        self.graph = init_graph(self.state(), first_to_move(self.record.moves.len()));
+        self.graph[0].can_swap = self.rules.swap && self.record.moves.len() == 1;
+        self.root = 0;
+        self.turn_start = Instant::now();
+        Ok(())
+    }
+
+    pub fn result(&self) -> GameResult {
+        if let Some(result) = self.decided {
+            return result;
+        }
+        let (p1, p2) = self.state();
+        // the status is for the player to move
+        match get_status(p1, p2, first_to_move(self.record.moves.len())) {
+            STATUS::PLAYING => GameResult::Playing,
+            STATUS::WON => GameResult::Won(self.to_move()),
+            STATUS::LOST => GameResult::Won(1 - self.to_move()),
+            STATUS::DRAW => GameResult::Drawn,
+        }
+    }
+}
+
+impl Drop for GameController {
+    fn drop(&mut self) {
+        self.abandon();
+    }
+}
+
+fn root_children(graph: &[Node], root: usize) -> impl Iterator<Item = ChildStats> + '_ {
+    graph[root].children().map(move |child| {
+        let node = &graph[child];
+        ChildStats {
+            name: move_name(graph[root].state, node.state),
+            visits: node.nb_visit,
+            mean: node.mean(),
+            // the node's status is for the player to move after the move
+            status: match node.status {
+                STATUS::WON => STATUS::LOST,
+                STATUS::LOST => STATUS::WON,
+                ref status => status.clone(),
+            },
+        }
+    })
+}
+
+fn node_count(graph: &[Node], root: usize) -> usize {
+    let mut count = 0;
+    let mut pending = vec![root];
+    while let Some(node) = pending.pop() {
+        count += 1;
+        pending.extend(graph[node].children());
+    }
+    count
+}
+
+// the engine's move, and the result when it ends the game
+fn engine_turn(game: &mut GameController) -> String {
+    let reply = match game.play_engine() {
+        Ok(EngineMove { name, eval }) => format!("engine {name} eval {eval:.4}"),
+        Err(error) => format!("error {error}"),
+    };
+    match result_line(game) {
+        Some(result) => format!("{reply}\n{result}"),
+        None => reply,
+    }
+}
+
+fn result_line(game: &GameController) -> Option<String> {
+    let winner = match game.result() {
+        GameResult::Playing => return None,
+        GameResult::Won(seat) => Some(seat),
+        GameResult::Drawn => None,
+    };
+    let [first, second] = game.clocks();
+    Some(format!(
+        "result {} time {first} {second}",
+        record::result_text(winner)
+    ))
+}
+
+fn handle(game: &mut GameController, line: &str) -> Option<String> {
+    let reply = match line.trim() {
+        "" => return Some(String::new()),
+        "quit" => return None,
+        "go" => engine_turn(game),
+        "undo" => {
+            // the player's seat is that of their last move (the engine's have their eval for a
+            // comment), whose turn it is again once the engine's reply, if any, is gone too
+            let seat = game
+                .record()
+                .moves
+                .iter()
+                .rposition(|(_, comment)| comment.is_none())
+                .map(|ply| ply % 2);
+            let mut undone = game.undo();
+            while undone.is_ok() && seat.is_some_and(|seat| game.to_move() != seat) {
+                undone = game.undo();
+            }
+            match undone {
+                Ok(()) => format!("moves {}", game.moves().join(" "))
+                    .trim_end()
+                    .to_string(),
+                Err(error) => format!("error {error}"),
+            }
+        }
+        text => {
+            let played = match parse_column(text, false) {
+                Some(column) => game.play_human(column),
+                None if text == "s" => game.swap(),
+                None => Err(MoveError::Unreadable.to_string()),
+            };
+            match played {
+                Ok(()) => match result_line(game) {
+                    Some(result) => result,
+                    None => engine_turn(game),
+                },
+                Err(error) => format!("error {error}"),
+            }
+        }
+    };
+    Some(reply)
+}
+
+pub fn main(options: Options) {
+    if let Some(seed) = options.seed {
+        random::seed(seed);
+    }
+    let time = match options.iterations {
+        Some(_) => u128::MAX,
+        None => TIME_PER_MOVE,
+    };
+    let mut game = GameController::new(options.rules, options.params, time, options.tt_mb)
+        .with_threads(options.threads);
+    if options.fast {
+        game = game.capped(options.max_memory_mb);
+    }
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        match handle(&mut game, &line) {
+            Some(reply) if reply.is_empty() => {}
+            Some(reply) => println!("{reply}"),
+            None => return,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_profile;
+
+    #[test]
+    fn views_of_the_engine_tree() {
+        let params = get_profile("default").unwrap();
+        let mut game = GameController::new(Rules { swap: false }, params, 50, 0);
+        // nothing searched yet
+        assert_eq!(game.node_count(), 1);
+        assert_eq!(game.root_children().count(), 0);
+        assert!(game.pv().is_empty());
+        // the engine's reply is what it searched most, and the player's turn keeps its tree
+        game.play_engine().unwrap();
+        let children: Vec<ChildStats> = game.root_children().collect();
+        assert_eq!(children.len(), 7);
+        assert!(game.node_count() > children.len());
+        let most = children.iter().map(|child| child.visits).max().unwrap();
+        let pv = game.pv();
+        assert!(children
+            .iter()
+            .any(|child| child.name == pv[0] && child.visits == most));
+    }
+
+    #[test]
+    fn undo_goes_back_to_the_players_turn() {
+        let params = get_profile("default").unwrap();
+        let mut game = GameController::new(Rules { swap: false }, params, 50, 0);
+        assert!(handle(&mut game, "4").unwrap().starts_with("engine "));
+        assert_eq!(handle(&mut game, "undo").unwrap(), "moves");
+        // the player's winning move got no reply, it alone is taken back
+        for column in [1, 2, 1, 2, 1, 2] {
+            game.play_human(column).unwrap();
+        }
+        assert!(handle(&mut game, "1").unwrap().starts_with("result 1-0"));
+        assert_eq!(handle(&mut game, "undo").unwrap(), "moves 1 2 1 2 1 2");
+        assert_eq!(game.result(), GameResult::Playing);
+    }
+}
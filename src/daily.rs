@@ -0,0 +1,85 @@
+// daily [OPTIONS]: the same middlegame for everyone on a given day, from seeded self-play,
+// the human to move and to win it against the bot (with the usual options). The result goes
+// to the player's statistics under the "daily" difficulty.
+use crate::{
+    board::{first_to_move, get_moves, is_over},
+    get_profile, init_graph, mcst, move_name, random,
+    record::today,
+    solver::SolverCache,
+    Limits, Params, Rules,
+};
+
+const PLIES: usize = 12;
+// played at random before the self-play, for the days to differ
+const RANDOM_PLIES: usize = 2;
+const SELF_PLAY_ITERATIONS: u64 = 2000;
+const CHECK_ITERATIONS: u64 = 20_000;
+// the evaluations for the human a challenge may start from: playable, without being won
+const BALANCE: (f64, f64) = (0.9, 1.4);
+const TRIES: usize = 50;
+
+// FNV-1a, the same on every platform
+fn seed(date: &str) -> u64 {
+    date.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn self_play(params: &Params, cache: &mut SolverCache) -> Option<((u64, u64), Vec<String>)> {
+    let mut state = (0, 0);
+    let mut moves = vec![];
+    while moves.len() < PLIES {
+        if is_over(state.0, state.1) {
+            return None;
+        }
+        let next = if moves.len() < RANDOM_PLIES {
+            let options = get_moves(state.0, state.1);
+            options[random::below(options.len())]
+        } else {
+            let limits = Limits::movetime(u128::MAX);
+            let rules = Rules { swap: false };
+            let graph = init_graph(state, first_to_move(moves.len()));
+            mcst(graph, 0, &limits, rules, params, cache).1
+        };
+        moves.push(move_name(state, next));
+        state = next;
+    }
+    (!is_over(state.0, state.1)).then_some((state, moves))
+}
+
+// the day's date and moves
+pub fn challenge() -> (String, Vec<String>) {
+    let date = today();
+    random::seed(seed(&date));
+    let default = get_profile("default").unwrap();
+    let play = Params {
+        max_iterations: Some(SELF_PLAY_ITERATIONS),
+        temperature: 0.1,
+        ..default
+    };
+    let check = Params {
+        max_iterations: Some(CHECK_ITERATIONS),
+        ..default
+    };
+    let mut cache = SolverCache::new(1);
+    let mut last = vec![];
+    for _ in 0..TRIES {
+        let Some((state, moves)) = self_play(&play, &mut cache) else {
+            continue;
+        };
+        let limits = Limits::movetime(u128::MAX);
+        let (score, _, _, _) = mcst(
+            init_graph(state, first_to_move(moves.len())),
+            0,
+            &limits,
+            Rules { swap: false },
+            &check,
+            &mut cache,
+        );
+        if (BALANCE.0..=BALANCE.1).contains(&score) {
+            return (date, moves);
+        }
+        last = moves;
+    }
+    (date, last)
+}
@@ -0,0 +1,290 @@
+// uci-dataset FILE [SEARCHED] [ITERATIONS]: reads the UCI machine learning repository's
+// connect-4 data set, where every line is a position and its game-theoretic outcome:
+//
+//     b,b,b,b,b,b,b,b,b,b,b,b,x,o,b,b,b,b,x,o,x,o,x,o,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,win
+//
+// the 42 cells go column by column from the left, bottom to top, x being the first player,
+// o the second and b an empty cell, and the outcome (win, loss or draw) is x's. The positions
+// are checked against the static evaluation, and the first SEARCHED of them against
+// fixed-iteration searches.
+use std::fs;
+
+use crate::{
+    board::{first_to_move, is_over, play_column},
+    eval, get_profile, init_graph, mcst, scale,
+    solver::SolverCache,
+    suite, tt, usage_error, Limits, Params, Rules,
+};
+
+const DEFAULT_SEARCHED: usize = 200;
+const DEFAULT_ITERATIONS: u64 = 2000;
+// the searches' mistakes shown, as test suite moves
+const SHOWN_MISSES: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+pub struct Record {
+    // columns (1 -> 7) reaching the position from the empty board
+    pub moves: Vec<u32>,
+    pub state: (u64, u64),
+    // for the player to move
+    pub outcome: Outcome,
+}
+
+// a play order building the position, x first: every disc goes on top of its column
+fn find_moves(cells: &[[char; 6]; 7], heights: &mut [usize; 7], moves: &mut Vec<u32>) -> bool {
+    let total: usize = cells
+        .iter()
+        .map(|column| column.iter().filter(|cell| **cell != 'b').count())
+        .sum();
+    if moves.len() == total {
+        return true;
+    }
+    let disc = if moves.len().is_multiple_of(2) {
+        'x'
+    } else {
+        'o'
+    };
+    for x in 0..7 {
+        if heights[x] < 6 && cells[x][heights[x]] == disc {
+            heights[x] += 1;
+            moves.push(x as u32 + 1);
+            if find_moves(cells, heights, moves) {
+                return true;
+            }
+            moves.pop();
+            heights[x] -= 1;
+        }
+    }
+    false
+}
+
+fn parse_line(line: &str) -> Option<Record> {
+    let fields: Vec<&str> = line.split(',').collect();
+    if fields.len() != 43 {
+        return None;
+    }
+    let mut cells = [['b'; 6]; 7];
+    for (i, field) in fields[..42].iter().enumerate() {
+        cells[i / 6][i % 6] = match *field {
+            "x" => 'x',
+            "o" => 'o',
+            "b" => 'b',
+            _ => return None,
+        };
+    }
+    // no floating discs
+    for column in &cells {
+        if column
+            .windows(2)
+            .any(|pair| pair[0] == 'b' && pair[1] != 'b')
+        {
+            return None;
+        }
+    }
+    let mut moves = vec![];
+    if !find_moves(&cells, &mut [0; 7], &mut moves) {
+        return None;
+    }
+    let mut state = (0, 0);
+    for column in &moves {
+        state = play_column(state.0, state.1, *column)?;
+    }
+    if is_over(state.0, state.1) {
+        return None;
+    }
+    let outcome = match (fields[42], moves.len().is_multiple_of(2)) {
+        ("draw", _) => Outcome::Draw,
+        ("win", true) | ("loss", false) => Outcome::Win,
+        ("loss", true) | ("win", false) => Outcome::Loss,
+        _ => return None,
+    };
+    Some(Record {
+        moves,
+        state,
+        outcome,
+    })
+}
+
+pub fn load(path: &str) -> Result<Vec<Record>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}"))?;
+    let mut records = vec![];
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Some(record) => records.push(record),
+            None => return Err(format!("{path}:{}: invalid record", n + 1)),
+        }
+    }
+    Ok(records)
+}
+
+// the outcome a score sits closest to, the score going from 0 (lost) to 2 (won)
+fn predicted(score: f64, draw_margin: f64) -> Outcome {
+    if score > 1.0 + draw_margin {
+        Outcome::Win
+    } else if score < 1.0 - draw_margin {
+        Outcome::Loss
+    } else {
+        Outcome::Draw
+    }
+}
+
+// the share of the records whose outcome matches the prediction, by outcome
+fn agreement(records: &[Record], mut predict: impl FnMut(&Record) -> Outcome) -> String {
+    let mut counts = [(0, 0); 3];
+    for record in records {
+        let i = record.outcome as usize;
+        counts[i].1 += 1;
+        if predict(record) == record.outcome {
+            counts[i].0 += 1;
+        }
+    }
+    let share = |(agree, total): (u32, u32)| {
+        if total == 0 {
+            "-".to_string()
+        } else {
+            format!("{:.1}%", 100.0 * agree as f64 / total as f64)
+        }
+    };
+    format!(
+        "wins {}, losses {}, draws {}",
+        share(counts[0]),
+        share(counts[1]),
+        share(counts[2])
+    )
+}
+
+pub fn main(args: &[String]) {
+    let path = args
+        .first()
+        .unwrap_or_else(|| usage_error("uci-dataset needs the data file"));
+    let parse = |i: usize, default: u64| match args.get(i) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| usage_error("uci-dataset expects numbers")),
+        None => default,
+    };
+    let searched = parse(1, DEFAULT_SEARCHED as u64) as usize;
+    let iterations = parse(2, DEFAULT_ITERATIONS);
+    let records = load(path).unwrap_or_else(|error| usage_error(&error));
+    let count = |outcome| records.iter().filter(|r| r.outcome == outcome).count();
+    println!(
+        "{} positions: {} won, {} lost and {} drawn for the player to move",
+        records.len(),
+        count(Outcome::Win),
+        count(Outcome::Loss),
+        count(Outcome::Draw)
+    );
+    // the evaluation is for the player who just moved
+    let static_agreement = agreement(&records, |record| {
+        let (p1, p2) = record.state;
+        predicted(1.0 - eval::evaluate(p1, p2), 0.1)
+    });
+    println!("static evaluation agrees on {static_agreement}");
+    let params = Params {
+        max_iterations: Some(iterations),
+        ..get_profile("default").unwrap()
+    };
+    let mut cache = SolverCache::new(tt::DEFAULT_MB);
+    let sample = &records[..searched.min(records.len())];
+    let mut misses = vec![];
+    let search_agreement = agreement(sample, |record| {
+        let (score, _, _, _) = mcst(
+            init_graph(record.state, first_to_move(record.moves.len())),
+            0,
+            &Limits::movetime(u128::MAX),
+            Rules { swap: false },
+            &params,
+            &mut cache,
+        );
+        let outcome = predicted(score, 0.1);
+        if outcome != record.outcome && misses.len() < SHOWN_MISSES {
+            misses.push(format!(
+                "{} is a {:?}, the search said {}",
+                suite::moves_to_string(&record.moves),
+                record.outcome,
+                scale::percent(score)
+            ));
+        }
+        outcome
+    });
+    println!(
+        "{iterations}-iteration searches agree on {search_agreement} of the first {}",
+        sample.len()
+    );
+    for miss in misses {
+        println!("  {miss}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a line of the data set from the columns' discs, bottom to top
+    fn line(columns: [&str; 7], outcome: &str) -> String {
+        let mut fields = vec![];
+        for column in columns {
+            let cells = column.chars().chain(std::iter::repeat('b'));
+            fields.extend(cells.take(6).map(String::from));
+        }
+        fields.push(outcome.to_string());
+        fields.join(",")
+    }
+
+    #[test]
+    fn accepted_lines() {
+        // the example of the data set's description
+        let example = "b,b,b,b,b,b,b,b,b,b,b,b,x,o,b,b,b,b,x,o,x,o,x,o,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,b,win";
+        assert_eq!(line(["", "", "xo", "xoxoxo", "", "", ""], "win"), example);
+        let record = parse_line(example).unwrap();
+        assert_eq!(record.moves.len(), 8);
+        let bits = |cells: &[(u32, u32)]| cells.iter().map(|(row, x)| 1 << (8 * row + x)).sum();
+        let x = bits(&[(0, 2), (0, 3), (2, 3), (4, 3)]);
+        let o = bits(&[(1, 2), (1, 3), (3, 3), (5, 3)]);
+        assert_eq!(record.state, (x, o));
+        assert_eq!(record.outcome, Outcome::Win);
+        // the outcome is x's, and o is to move
+        let record = parse_line(&line(["", "", "", "x", "", "", ""], "loss")).unwrap();
+        assert_eq!((record.moves, record.state), (vec![4], (0, 1 << 3)));
+        assert_eq!(record.outcome, Outcome::Win);
+        let record = parse_line(&line(["o", "", "", "x", "", "", ""], "draw")).unwrap();
+        assert_eq!(record.outcome, Outcome::Draw);
+    }
+
+    #[test]
+    fn rejected_lines() {
+        let empty = ["", "", "", "", "", "", ""];
+        // no outcome, or another one
+        assert!(parse_line(&line(empty, "win")[..2 * 42 - 1]).is_none());
+        assert!(parse_line(&line(empty, "maybe")).is_none());
+        assert!(parse_line(&line(["y", "", "", "", "", "", ""], "win")).is_none());
+        assert!(parse_line(&line(["bx", "o", "", "", "", "", ""], "win")).is_none());
+        // no play order reaches two discs of x's and none of o's
+        assert!(parse_line(&line(["x", "x", "", "", "", "", ""], "win")).is_none());
+        // a game already over
+        assert!(parse_line(&line(["xxxx", "ooo", "", "", "", "", ""], "win")).is_none());
+    }
+
+    #[test]
+    fn load_skips_blank_lines_and_tells_the_bad_one() {
+        let path = std::env::temp_dir().join(format!("mcst-dataset-{}", std::process::id()));
+        let good = line(["", "", "", "x", "", "", ""], "loss");
+        fs::write(&path, format!("{good}\n\n{good}\n")).unwrap();
+        assert_eq!(load(path.to_str().unwrap()).unwrap().len(), 2);
+        fs::write(&path, format!("{good}\n{good}\nx,o\n")).unwrap();
+        let error = load(path.to_str().unwrap()).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(error.ends_with(":3: invalid record"), "{error}");
+    }
+}
@@ -0,0 +1,97 @@
+// Plain sentences instead of the grid, for screen readers (--describe):
+//
+//     column 4 now has 3 discs
+//     you threaten row 2, columns 3 and 6
+//
+// rows and columns are counted from 1, rows from the bottom. The board command lists every
+// column the same way.
+use crate::eval::threats;
+
+const COLUMN: u64 = 0x0101_0101_0101;
+
+// how to speak of one player, as in "you threaten" and "column 1 from the bottom: yours"
+pub struct Name {
+    pub subject: &'static str,
+    pub owner: &'static str,
+}
+
+pub const YOU: Name = Name {
+    subject: "you",
+    owner: "yours",
+};
+
+pub const ME: Name = Name {
+    subject: "I",
+    owner: "mine",
+};
+
+fn join(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [rest @ .., last] => format!("{} and {last}", rest.join(", ")),
+    }
+}
+
+fn discs(count: u32) -> String {
+    match count {
+        0 => "no discs".to_string(),
+        1 => "1 disc".to_string(),
+        _ => format!("{count} discs"),
+    }
+}
+
+// the empty cells that would complete a four for the player, row by row
+fn describe_threats(player: u64, grid: u64, name: &Name) -> Option<String> {
+    let cells = threats(player, grid);
+    let rows: Vec<String> = (0..6)
+        .filter_map(|y| {
+            let columns: Vec<String> = (0..7)
+                .filter(|x| 1 << (y * 8 + x) & cells != 0)
+                .map(|x| (x + 1).to_string())
+                .collect();
+            match columns.len() {
+                0 => None,
+                1 => Some(format!("row {}, column {}", y + 1, columns[0])),
+                _ => Some(format!("row {}, columns {}", y + 1, join(&columns))),
+            }
+        })
+        .collect();
+    (!rows.is_empty()).then(|| format!("{} threaten {}", name.subject, rows.join("; ")))
+}
+
+// after a move in `column` (None for the swap), p1 being the player to move
+pub fn describe_move(p1: u64, p2: u64, column: Option<u32>, names: [&Name; 2]) -> Vec<String> {
+    let grid = p1 | p2;
+    let mut lines = vec![];
+    if let Some(column) = column {
+        let count = (grid & COLUMN << (column - 1)).count_ones();
+        let full = if count == 6 { ", it is full" } else { "" };
+        lines.push(format!("column {column} now has {}{full}", discs(count)));
+    }
+    for (player, name) in [(p2, names[1]), (p1, names[0])] {
+        lines.extend(describe_threats(player, grid, name));
+    }
+    lines
+}
+
+// every column from the bottom up
+pub fn describe_board(p1: u64, p2: u64, names: [&Name; 2]) -> Vec<String> {
+    (0..7)
+        .map(|x| {
+            let owners: Vec<String> = (0..6)
+                .map(|y| 1 << (y * 8 + x))
+                .take_while(|cell| cell & (p1 | p2) != 0)
+                .map(|cell| {
+                    let name = if cell & p1 != 0 { names[0] } else { names[1] };
+                    name.owner.to_string()
+                })
+                .collect();
+            if owners.is_empty() {
+                format!("column {} is empty", x + 1)
+            } else {
+                format!("column {} from the bottom: {}", x + 1, owners.join(", "))
+            }
+        })
+        .collect()
+}
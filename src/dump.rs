@@ -0,0 +1,313 @@
+// Search trees can be dumped for offline debugging, in a chunks.rs file with one TREE chunk:
+//
+//     <root: u32>  <nodes: u32>  then for every node, in index order:
+//     <p1: u64>  <p2: u64>  <parent: u32>  <score: f32>  <nb_visit: u32>  <first child: u32>
+//     <children: u8>  <status: u8>  <can_swap: u8>
+//
+// p1 being the player to move, a parent of u32::MAX none, the status the index in STATUSES
+// (for the player to move) and can_swap 0 or 1. The dumps of older builds were text files,
+// which still load:
+//
+//     mcst-tree 2
+//     root <index>
+//     node <index> <p1> <p2> <parent> <score> <nb_visit> <status> <can_swap> <children>...
+//
+// one `node` line per arena entry, the children of a node being consecutive, the bitboards in
+// hexadecimal, `-` for no parent and the status's name. Version 1 had the same lines with the
+// children anywhere in the arena; its trees are laid out again below the root, whatever the
+// root doesn't reach being dropped.
+use std::fs;
+
+use crate::{
+    board::{check_position, set_strict, STATUS},
+    chunks::{self, Reader, Tag},
+    explain, move_name, usage_error, Node,
+};
+
+const HEADER: &str = "mcst-tree 2";
+const HEADER_V1: &str = "mcst-tree 1";
+const TREE: Tag = *b"TREE";
+const STATUSES: [STATUS; 4] = [STATUS::PLAYING, STATUS::WON, STATUS::LOST, STATUS::DRAW];
+
+pub fn status_name(status: &STATUS) -> &'static str {
+    match status {
+        STATUS::PLAYING => "playing",
+        STATUS::WON => "won",
+        STATUS::LOST => "lost",
+        STATUS::DRAW => "draw",
+    }
+}
+
+fn parse_status(name: &str) -> Option<STATUS> {
+    match name {
+        "playing" => Some(STATUS::PLAYING),
+        "won" => Some(STATUS::WON),
+        "lost" => Some(STATUS::LOST),
+        "draw" => Some(STATUS::DRAW),
+        _ => None,
+    }
+}
+
+pub fn save(path: &str, graph: &[Node], root: usize) -> Result<(), String> {
+    let mut data = vec![];
+    data.extend((root as u32).to_le_bytes());
+    data.extend((graph.len() as u32).to_le_bytes());
+    for node in graph {
+        data.extend(node.state.0.to_le_bytes());
+        data.extend(node.state.1.to_le_bytes());
+        data.extend(node.parent.unwrap_or(u32::MAX).to_le_bytes());
+        data.extend(node.score.to_le_bytes());
+        data.extend(node.nb_visit.to_le_bytes());
+        data.extend(node.first_child.to_le_bytes());
+        let status = STATUSES.iter().position(|x| *x == node.status).unwrap();
+        data.extend([node.nb_children, status as u8, node.can_swap as u8]);
+    }
+    chunks::write(path, &[(TREE, data)])
+}
+
+fn read_node(reader: &mut Reader) -> Result<Option<Node>, String> {
+    let state = (reader.u64()?, reader.u64()?);
+    let parent = reader.u32()?;
+    let score = reader.f32()?;
+    let nb_visit = reader.u32()?;
+    let first_child = reader.u32()?;
+    let nb_children = reader.u8()?;
+    let Some(status) = STATUSES.get(reader.u8()? as usize) else {
+        return Ok(None);
+    };
+    let can_swap = match reader.u8()? {
+        0 => false,
+        1 => true,
+        _ => return Ok(None),
+    };
+    Ok(Some(Node {
+        state,
+        score,
+        nb_visit,
+        parent: (parent != u32::MAX).then_some(parent),
+        first_child,
+        nb_children,
+        status: status.clone(),
+        can_swap,
+        // not dumped, nothing reads them outside of a search
+        first: true,
+        evaluation: 0.0,
+    }))
+}
+
+fn read_tree(path: &str, data: &[u8]) -> Result<(Vec<Node>, usize), String> {
+    let mut reader = Reader::new(path, data);
+    let root = reader.u32()? as usize;
+    let nb_nodes = reader.u32()?;
+    let mut graph = vec![];
+    for i in 0..nb_nodes {
+        let node = read_node(&mut reader)?.ok_or_else(|| format!("{path}: invalid node {i}"))?;
+        graph.push(node);
+    }
+    Ok((graph, root))
+}
+
+// the node and its children, which `contiguous` requires to be consecutive
+fn parse_node(fields: &[&str], index: usize, contiguous: bool) -> Option<(Node, Vec<u32>)> {
+    if fields.len() < 9 || fields[0] != "node" || fields[1].parse() != Ok(index) {
+        return None;
+    }
+    let children = fields[9..]
+        .iter()
+        .map(|child| child.parse().ok())
+        .collect::<Option<Vec<u32>>>()?;
+    let first_child = children.first().copied().unwrap_or(0);
+    // (a crafted first child near u32::MAX has no room for the rest)
+    if contiguous
+        && children
+            .iter()
+            .enumerate()
+            .any(|(i, child)| first_child.checked_add(i as u32) != Some(*child))
+    {
+        return None;
+    }
+    let node = Node {
+        state: (
+            u64::from_str_radix(fields[2], 16).ok()?,
+            u64::from_str_radix(fields[3], 16).ok()?,
+        ),
+        score: fields[5].parse().ok()?,
+        nb_visit: fields[6].parse().ok()?,
+        parent: match fields[4] {
+            "-" => None,
+            parent => Some(parent.parse().ok()?),
+        },
+        first_child,
+        nb_children: children.len().try_into().ok()?,
+        status: parse_status(fields[7])?,
+        can_swap: match fields[8] {
+            "0" => false,
+            "1" => true,
+            _ => return None,
+        },
+        first: true,
+        evaluation: 0.0,
+    };
+    Some((node, children))
+}
+
+// a version 1 arena, breadth first from the root so the children of a node are consecutive
+fn lay_out(path: &str, nodes: Vec<(Node, Vec<u32>)>, root: usize) -> Result<Vec<Node>, String> {
+    if root >= nodes.len() {
+        return Err(format!("{path}: node index out of range"));
+    }
+    let mut seen = vec![false; nodes.len()];
+    seen[root] = true;
+    // each node with its new parent
+    let mut order = vec![(root, None)];
+    let mut graph = vec![];
+    while let Some(&(old, parent)) = order.get(graph.len()) {
+        let (node, children) = &nodes[old];
+        let mut node = node.clone();
+        node.parent = parent;
+        node.first_child = order.len() as u32;
+        for child in children {
+            let child = *child as usize;
+            match seen.get(child) {
+                Some(false) => {}
+                Some(true) => return Err(format!("{path}: node {child} has two parents")),
+                None => return Err(format!("{path}: node index out of range")),
+            }
+            seen[child] = true;
+            order.push((child, Some(graph.len() as u32)));
+        }
+        graph.push(node);
+    }
+    Ok(graph)
+}
+
+// an older build's text dump
+fn parse_text(path: &str, content: &str) -> Result<(Vec<Node>, usize), String> {
+    let mut lines = content.lines();
+    let contiguous = match lines.next() {
+        Some(HEADER) => true,
+        Some(HEADER_V1) => false,
+        _ => return Err(format!("{path} is not a search tree dump")),
+    };
+    let root = lines
+        .next()
+        .and_then(|line| line.strip_prefix("root "))
+        .and_then(|root| root.parse().ok())
+        .ok_or_else(|| format!("{path}:2: missing root"))?;
+    let mut nodes = vec![];
+    for (n, line) in lines.enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let node = parse_node(&fields, nodes.len(), contiguous)
+            .ok_or_else(|| format!("{path}:{}: invalid node", n + 3))?;
+        nodes.push(node);
+    }
+    if !contiguous {
+        return Ok((lay_out(path, nodes, root)?, 0));
+    }
+    Ok((nodes.into_iter().map(|(node, _)| node).collect(), root))
+}
+
+pub fn load(path: &str) -> Result<(Vec<Node>, usize), String> {
+    let content = fs::read(path).map_err(|error| format!("can't read {path}: {error}"))?;
+    let (graph, root) = match chunks::read(path, &content, &[TREE])? {
+        Some(chunks) => {
+            let (_, data) = chunks
+                .iter()
+                .find(|(tag, _)| *tag == TREE)
+                .ok_or_else(|| format!("{path} has no tree"))?;
+            read_tree(path, data)?
+        }
+        None => match String::from_utf8(content) {
+            Ok(content) => parse_text(path, &content)?,
+            Err(_) => return Err(format!("{path} is not a search tree dump")),
+        },
+    };
+    for (i, node) in graph.iter().enumerate() {
+        check_position(node.state).map_err(|error| format!("{path}: node {i}: {error}"))?;
+    }
+    // every index has to point inside the arena
+    let nb_nodes = graph.len();
+    let valid = root < nb_nodes
+        && graph.iter().all(|node| {
+            node.parent().is_none_or(|parent| parent < nb_nodes) && node.children().end <= nb_nodes
+        });
+    if !valid {
+        return Err(format!("{path}: node index out of range"));
+    }
+    Ok((graph, root))
+}
+
+// `inspect-tree FILE [--strict]`
+pub fn inspect(args: &[String]) {
+    set_strict(args.iter().any(|arg| arg == "--strict"));
+    let path = args
+        .first()
+        .unwrap_or_else(|| usage_error("inspect-tree needs a file"));
+    let (graph, root) = load(path).unwrap_or_else(|error| usage_error(&error));
+    println!("{} nodes, root {root}", graph.len());
+    for status in [STATUS::PLAYING, STATUS::WON, STATUS::LOST, STATUS::DRAW] {
+        let count = graph.iter().filter(|node| node.status == status).count();
+        println!("  {}: {count}", status_name(&status));
+    }
+    let node = &graph[root];
+    println!(
+        "root: {} visits, score {}, {}",
+        node.nb_visit,
+        node.score,
+        status_name(&node.status)
+    );
+    for child in node.children() {
+        let child_node = &graph[child];
+        println!(
+            "  {:>4}: {:>8} visits, mean {:.3}, {}",
+            move_name(node.state, child_node.state),
+            child_node.nb_visit,
+            child_node.mean(),
+            status_name(&child_node.status)
+        );
+    }
+    println!(
+        "main line: {}",
+        explain::principal_variation(&graph, root).join(" ")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the root's children at 3 and 1, and a node out of the tree at 2
+    const V1: &str = "mcst-tree 1
+root 0
+node 0 0 0 - 3 4 playing 0 3 1
+node 1 0 8 0 1 2 playing 0
+node 2 0 4 - 0 0 playing 0
+node 3 0 1 0 2 2 playing 0
+";
+
+    #[test]
+    fn version_1_is_laid_out_again() {
+        let (graph, root) = parse_text("v1", V1).unwrap();
+        assert_eq!((graph.len(), root), (3, 0));
+        assert_eq!(graph[0].children(), 1..3);
+        assert_eq!(graph[1].state, (0, 1));
+        assert_eq!(graph[2].state, (0, 8));
+        assert!(graph[1..].iter().all(|node| node.parent() == Some(0)));
+    }
+
+    #[test]
+    fn version_2_needs_consecutive_children() {
+        let v2 = V1.replacen("mcst-tree 1", "mcst-tree 2", 1);
+        assert!(parse_text("v2", &v2).is_err());
+    }
+
+    #[test]
+    fn children_past_the_last_index() {
+        let text = format!(
+            "mcst-tree 2\nroot 0\nnode 0 0 0 - 3 4 playing 0 {} {}\n",
+            u32::MAX,
+            u32::MAX
+        );
+        assert!(parse_text("v2", &text).is_err());
+    }
+}
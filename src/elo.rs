@@ -0,0 +1,107 @@
+// estimate-elo [GAMES] [MS] [PROFILE]: plays the engine against the built-in opponents, GAMES
+// games each at MS milliseconds a move, and rates it from the results on a scale where the
+// random mover is 0. The opponents' own ratings come from 60-game matches between neighbours
+// of the ladder, so the estimate only compares with other estimates, not with human ratings.
+use crate::{
+    get_profile,
+    matches::{self, Player, Settings},
+    opponents::{Engine, Greedy, Minimax, RandomMover},
+    tt, usage_error, Rules,
+};
+
+const DEFAULT_GAMES: u32 = 10;
+const DEFAULT_TIME: u128 = 100; // milliseconds
+
+// the random first moves of each pair of games
+const OPENING_PLIES: usize = 2;
+
+fn ladder() -> Vec<(Box<dyn Player>, f64)> {
+    vec![
+        (Box::new(RandomMover::new()), 0.0),
+        (Box::new(Greedy::new()), 550.0),
+        (Box::new(Minimax::new(3)), 1250.0),
+        (Box::new(Minimax::new(5)), 1450.0),
+        (Box::new(Engine::with_iterations(1000)), 1550.0),
+        (Box::new(Engine::with_iterations(5000)), 1700.0),
+        (Box::new(Engine::with_iterations(20000)), 1850.0),
+    ]
+}
+
+// the expected score against an opponent
+fn expected(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+// the rating whose expected scores add up to the points, and its standard error, from each
+// opponent's rating, games and points
+pub fn estimate(results: &[(f64, u32, f64)]) -> (f64, f64) {
+    // half a point more over one game more against each, so that a clean sweep has a rating
+    let target: f64 = results
+        .iter()
+        .map(|(_, games, points)| (points + 0.5) / (*games as f64 + 1.0) * *games as f64)
+        .sum();
+    let total = |rating: f64| -> f64 {
+        results
+            .iter()
+            .map(|(opponent, games, _)| *games as f64 * expected(rating, *opponent))
+            .sum()
+    };
+    let (mut low, mut high) = (-2000.0, 5000.0);
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if total(middle) < target {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    let rating = (low + high) / 2.0;
+    let information: f64 = results
+        .iter()
+        .map(|(opponent, games, _)| {
+            let p = expected(rating, *opponent);
+            *games as f64 * p * (1.0 - p)
+        })
+        .sum();
+    (rating, 400.0 / 10f64.ln() / information.sqrt())
+}
+
+pub fn main(args: &[String]) {
+    let parse = |i: usize, name: &str| {
+        args.get(i).map(|value| {
+            value
+                .parse::<u128>()
+                .unwrap_or_else(|_| usage_error(&format!("the {name} must be a number")))
+        })
+    };
+    let games = parse(0, "number of games").map_or(DEFAULT_GAMES, |games| games as u32);
+    let time = parse(1, "time per move").unwrap_or(DEFAULT_TIME);
+    let profile = args.get(2).map_or("default", String::as_str);
+    let params =
+        get_profile(profile).unwrap_or_else(|| usage_error(&format!("unknown profile: {profile}")));
+    let mut engine = Engine::new(profile, params, tt::DEFAULT_MB);
+    let settings = Settings {
+        games,
+        rules: Rules { swap: false },
+        time,
+        adjudication: None,
+        openings: (0..games.div_ceil(2))
+            .map(|_| matches::random_opening(OPENING_PLIES))
+            .collect(),
+        record: None,
+        book: None,
+    };
+    let mut results = vec![];
+    for (mut opponent, rating) in ladder() {
+        let name = opponent.name();
+        let points = matches::run_match(&mut [&mut engine, opponent.as_mut()], &settings);
+        println!("against {name} ({rating:.0}): {}/{games}", points[0]);
+        results.push((rating, games, points[0]));
+    }
+    let (rating, error) = estimate(&results);
+    println!(
+        "{} at {time}ms a move: about {rating:.0} elo, +/- {:.0} (95%)",
+        engine.name(),
+        1.96 * error
+    );
+}
@@ -0,0 +1,27 @@
+// a static evaluation from the threats on the board, for the player who just moved (p2):
+// from -1 (looks lost) to 1 (looks won)
+use crate::board::FULL_GRID;
+
+const CENTER_COLUMN: u64 = 0x080808080808;
+
+// the empty cells completing a four for the player, the four directions at once
+pub fn threats(player: u64, grid: u64) -> u64 {
+    let mut cells = 0;
+    for step in [1, 7, 8, 9] {
+        let before = |n: u32| player << (step * n);
+        let after = |n: u32| player >> (step * n);
+        cells |= before(1) & before(2) & before(3);
+        cells |= after(1) & after(2) & after(3);
+        cells |= before(1) & before(2) & after(1);
+        cells |= after(1) & after(2) & before(1);
+    }
+    cells & FULL_GRID & !grid
+}
+
+pub fn evaluate(p1: u64, p2: u64) -> f64 {
+    let grid = p1 | p2;
+    let threats = threats(p2, grid).count_ones() as f64 - threats(p1, grid).count_ones() as f64;
+    let center =
+        (p2 & CENTER_COLUMN).count_ones() as f64 - (p1 & CENTER_COLUMN).count_ones() as f64;
+    (0.5 * threats + 0.1 * center).tanh()
+}
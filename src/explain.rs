@@ -0,0 +1,85 @@
+use crate::{board::Board, eval::threats, move_name, scale, Node};
+
+// the cells a disc would land on right now
+fn playable_cells(p1: u64, p2: u64) -> u64 {
+    Board::new(p1, p2)
+        .moves()
+        .fold(0, |cells, option| cells | option.cell)
+}
+
+fn describe_cells(cells: u64, playable: u64) -> String {
+    if cells == 0 {
+        return "none".to_string();
+    }
+    (0..48)
+        .filter(|i| 1 << i & cells != 0)
+        .map(|i| {
+            let now = if 1 << i & playable != 0 {
+                " (playable now)"
+            } else {
+                ""
+            };
+            format!("column {} row {}{now}", i % 8 + 1, i / 8 + 1)
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// follows the most visited children
+pub fn principal_variation(graph: &[Node], mut node: usize) -> Vec<String> {
+    let mut line = vec![];
+    while let Some(child) = graph[node]
+        .children()
+        .filter(|child| graph[*child].nb_visit > 0)
+        .max_by_key(|child| graph[*child].nb_visit)
+    {
+        line.push(move_name(graph[node].state, graph[child].state));
+        node = child;
+    }
+    line
+}
+
+// what the bot's last move (previous_state -> graph[root].state) did and how sure it was
+pub fn explain_move(
+    graph: &[Node],
+    previous_state: (u64, u64),
+    previous_root: usize,
+    root: usize,
+    source: &str,
+) {
+    // the bot was p1 before its move and is the second player after it
+    let (p1, p2) = previous_state;
+    let (opponent, bot) = graph[root].state;
+    println!("explanation:");
+    println!("  source: {source}");
+    let created = threats(bot, opponent | bot) & !threats(p1, p1 | p2);
+    println!(
+        "  threats created: {}",
+        describe_cells(created, playable_cells(opponent, bot))
+    );
+    let blocked = threats(p2, p1 | p2) & playable_cells(p1, p2) & bot;
+    println!("  threats blocked: {}", describe_cells(blocked, blocked));
+    if graph[previous_root].state != previous_state || graph[root].parent() != Some(previous_root) {
+        // the move wasn't searched from this position, there are no statistics to show
+        return;
+    }
+    let mut line = vec![move_name(previous_state, graph[root].state)];
+    line.extend(principal_variation(graph, root));
+    println!("  main line: {}", line.join(" "));
+    let second = graph[previous_root]
+        .children()
+        .filter(|child| *child != root && graph[*child].nb_visit > 0)
+        .max_by(|a, b| graph[*a].mean().total_cmp(&graph[*b].mean()));
+    if let Some(second) = second {
+        println!(
+            "  second best: {}, evaluation {} against {} ({} visits against {})",
+            move_name(previous_state, graph[second].state),
+            scale::percent(graph[second].mean()),
+            scale::percent(graph[root].mean()),
+            graph[second].nb_visit,
+            graph[root].nb_visit,
+        );
+    } else {
+        println!("  second best: none, this was the only move");
+    }
+}
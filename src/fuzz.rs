@@ -0,0 +1,187 @@
+// `fuzz [GAMES]`: plays many quick games, half of them random and half of them searched,
+// checking the boards and the search tree after every move.
+use rand::Rng;
+
+use crate::{
+    advance_root,
+    board::{get_moves, get_status, hash_state, is_over, is_winning, Board, FULL_GRID, STATUS},
+    collect_garbage, eval, get_node_moves, get_profile, init_graph, mcst,
+    solver::{self, SolverCache},
+    usage_error, Limits, Node, Params, Rules,
+};
+
+const SEARCH_ITERATIONS: u64 = 200;
+
+fn check_board(p1: u64, p2: u64) -> Result<(), String> {
+    if p1 & p2 != 0 {
+        return Err(format!("overlapping bitboards {p1:x} and {p2:x}"));
+    }
+    if (p1 | p2) & !FULL_GRID != 0 {
+        return Err(format!("discs outside the grid in {p1:x} / {p2:x}"));
+    }
+    // the shifted masks against a disc tried on every empty cell
+    // (a player who already has a four wins anywhere)
+    for player in [p1, p2].into_iter().filter(|player| !is_winning(*player)) {
+        let expected = (0..48)
+            .map(|i| 1u64 << i)
+            .filter(|cell| cell & FULL_GRID & !(p1 | p2) != 0 && is_winning(player | cell))
+            .fold(0, |cells, cell| cells | cell);
+        if eval::threats(player, p1 | p2) != expected {
+            return Err(format!("wrong threats for {player:x} in {p1:x} / {p2:x}"));
+        }
+    }
+    Ok(())
+}
+
+fn check_move(previous: (u64, u64), state: (u64, u64), can_swap: bool) -> Result<(), String> {
+    if can_swap && previous == state {
+        return Ok(());
+    }
+    let disc = state.1 ^ previous.0;
+    let grid = previous.0 | previous.1;
+    let legal = state.0 == previous.1
+        && state.1 & previous.0 == previous.0
+        && disc.count_ones() == 1
+        && disc & grid == 0
+        // it has to land on the bottom row or on another disc
+        && (disc & 0b1111111 != 0 || disc >> 8 & grid != 0);
+    if !legal {
+        return Err(format!("illegal move from {previous:x?} to {state:x?}"));
+    }
+    Ok(())
+}
+
+fn check_node(graph: &[Node], i: usize) -> Result<(), String> {
+    let node = &graph[i];
+    let (p1, p2) = node.state;
+    check_board(p1, p2)?;
+    let expected = get_status(p1, p2, node.first);
+    if expected != STATUS::PLAYING && expected != node.status {
+        return Err(format!("node {i} has the wrong status for a finished game"));
+    }
+    if is_winning(p1) {
+        return Err(format!("node {i}: the player to move has already won"));
+    }
+    let nb_children = node.nb_children as usize;
+    if nb_children != 0 && nb_children != get_node_moves(node).len() {
+        return Err(format!("node {i}: some of its moves are missing"));
+    }
+    if node.children().end > graph.len() {
+        return Err(format!("node {i}: its children are outside of the arena"));
+    }
+    for child in node.children() {
+        if graph[child].parent() != Some(i) {
+            return Err(format!("node {i}: child {child} has another parent"));
+        }
+        check_move(node.state, graph[child].state, node.can_swap)?;
+    }
+    if expected == STATUS::PLAYING && nb_children == 0 && node.status != STATUS::PLAYING {
+        // solved when it was expanded, the solver has to agree again
+        let solved = solver::status(solver::solve(p1, p2, &mut SolverCache::new(1)));
+        if solved != node.status {
+            return Err(format!("node {i}: solved to the wrong status"));
+        }
+    } else if expected == STATUS::PLAYING {
+        let children = || node.children().map(|child| &graph[child].status);
+        let complete = nb_children > 0;
+        let consistent = match node.status {
+            STATUS::PLAYING => !children().any(|status| *status == STATUS::LOST),
+            STATUS::WON => children().any(|status| *status == STATUS::LOST),
+            STATUS::LOST => complete && children().all(|status| *status == STATUS::WON),
+            STATUS::DRAW => {
+                complete
+                    && children().all(|status| *status == STATUS::WON || *status == STATUS::DRAW)
+            }
+        };
+        if !consistent {
+            return Err(format!(
+                "node {i}: proven status doesn't match its children"
+            ));
+        }
+    }
+    Ok(())
+}
+
+// the search only ever adds to the statistics
+fn check_search(before: &[(f32, u32)], graph: &[Node]) -> Result<(), String> {
+    for (i, (score, nb_visit)) in before.iter().enumerate() {
+        if graph[i].score < *score || graph[i].nb_visit < *nb_visit {
+            return Err(format!("node {i}: statistics went down during the search"));
+        }
+    }
+    (0..graph.len()).try_for_each(|i| check_node(graph, i))
+}
+
+fn play_game(searched: bool, rules: Rules, params: &Params) -> Result<usize, String> {
+    let mut state = (0, 0);
+    let mut graph = init_graph(state, true);
+    let mut root = 0;
+    let mut board = Board::new(0, 0);
+    let mut cache = SolverCache::new(1);
+    let mut plies = 0;
+    while !is_over(state.0, state.1) {
+        let previous = state;
+        let can_swap = graph[root].can_swap;
+        if searched {
+            (graph, root) = collect_garbage(graph, root);
+            let before: Vec<(f32, u32)> = graph
+                .iter()
+                .map(|node| (node.score, node.nb_visit))
+                .collect();
+            (_, state, graph, root) = mcst(
+                graph,
+                root,
+                &Limits::movetime(u128::MAX),
+                rules,
+                params,
+                &mut cache,
+            );
+            check_search(&before, &graph)?;
+        } else {
+            let moves = get_moves(state.0, state.1);
+            state = moves[rand::thread_rng().gen_range(0..moves.len())];
+            root = advance_root(&mut graph, root, state, rules);
+        }
+        check_board(state.0, state.1)?;
+        check_move(previous, state, can_swap)?;
+        board = board.after(state);
+        if board.hash() != hash_state(state.0, state.1) {
+            return Err(format!("incremental hash out of sync at {state:x?}"));
+        }
+        if graph[root].state != state {
+            return Err("the root doesn't follow the game".to_string());
+        }
+        plies += 1;
+    }
+    if !is_winning(state.1) && state.0 | state.1 != FULL_GRID {
+        return Err("the game stopped before its end".to_string());
+    }
+    Ok(plies)
+}
+
+pub fn main(args: &[String]) {
+    let games: u32 = match args.first() {
+        Some(games) => games
+            .parse()
+            .unwrap_or_else(|_| usage_error("the number of games must be a number")),
+        None => 1000,
+    };
+    let params = Params {
+        max_iterations: Some(SEARCH_ITERATIONS),
+        ..get_profile("default").unwrap()
+    };
+    let mut plies = 0;
+    for game in 0..games {
+        let rules = Rules {
+            swap: rand::thread_rng().gen_bool(0.5),
+        };
+        match play_game(game % 2 == 1, rules, &params) {
+            Ok(game_plies) => plies += game_plies,
+            Err(error) => {
+                eprintln!("game {}: {error}", game + 1);
+                std::process::exit(1);
+            }
+        }
+    }
+    println!("{games} games, {plies} moves, no invariant broken");
+}
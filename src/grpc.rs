@@ -0,0 +1,257 @@
+// gRPC service (proto/connect4.proto), only built with the `grpc` feature
+// tonic::Status is big, but it is what the service has to return
+#![allow(clippy::result_large_err)]
+
+use std::{
+    collections::HashMap,
+    mem,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{self, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{transport::Server, Request, Response, Status};
+
+use crate::{
+    board::{first_to_move, get_status, parse_move},
+    dump, get_profile, init_graph, move_name,
+    search::{search, Position},
+    solver::SolverCache,
+    tt, usage_error, GameController, GameResult, Limits, Rules,
+};
+
+pub mod proto {
+    tonic::include_proto!("connect4");
+}
+
+use proto::{
+    engine_server::{Engine, EngineServer},
+    AnalysisInfo, AnalyzeRequest, BestMoveReply, BestMoveRequest, EndGameRequest, NewGameReply,
+    NewGameRequest, PlayRequest, PositionReply,
+};
+
+// how long a game nobody plays or searches is kept, the next new game dropping it past that
+const IDLE_GAME: Duration = Duration::from_secs(60 * 60);
+
+// a client's game, whose searches run on the controller's tree
+struct Game {
+    controller: GameController,
+}
+
+impl Game {
+    fn play(&mut self, state: (u64, u64), comment: Option<String>) -> Result<(), String> {
+        self.controller.play(state, comment)?;
+        // what the other moves grew is out of reach now, and the root is back at 0
+        self.controller.compact();
+        Ok(())
+    }
+
+    // searches for `time` milliseconds more, returning the score, the best move and its visits;
+    // a search cancelled by a client going away leaves a new tree behind
+    async fn search(&mut self, time: u128) -> (f64, (u64, u64), u32) {
+        let state = self.controller.state();
+        let stats = self.controller.stats();
+        let (rules, params) = (self.controller.rules(), self.controller.params());
+        let (graph, root, cache) = self.controller.parts();
+        let mut fresh = init_graph(state, graph[root].first);
+        fresh[0].can_swap = graph[root].can_swap;
+        let position = Position {
+            graph: mem::replace(graph, fresh),
+            root,
+            rules,
+            params,
+            cache: mem::replace(cache, SolverCache::new(tt::DEFAULT_MB)),
+        };
+        let limits = Limits::movetime(time).with_stats(stats);
+        let result = search(position, limits).await;
+        let Position { graph, cache, .. } = result.position;
+        let visits = graph[root]
+            .children()
+            .find(|child| graph[*child].state == result.best)
+            .map_or(0, |child| graph[child].nb_visit);
+        let (tree, _, table) = self.controller.parts();
+        (*tree, *table) = (graph, cache);
+        (result.score, result.best, visits)
+    }
+
+    fn position(&self) -> PositionReply {
+        let (p1, p2) = self.controller.state();
+        let moves = self.controller.moves();
+        let first = first_to_move(moves.len());
+        PositionReply {
+            moves: moves.join(" "),
+            status: dump::status_name(&get_status(p1, p2, first)).to_string(),
+        }
+    }
+}
+
+// the games' locks are held across the searches' awaits
+type SharedGame = Arc<sync::Mutex<Game>>;
+
+#[derive(Default)]
+struct Service {
+    // with when a request last asked for them
+    games: Mutex<HashMap<u64, (SharedGame, Instant)>>,
+    next_id: AtomicU64,
+}
+
+impl Service {
+    fn game(&self, id: u64) -> Result<SharedGame, Status> {
+        let mut games = self.games.lock().unwrap();
+        let (game, used) = games
+            .get_mut(&id)
+            .ok_or_else(|| Status::not_found(format!("no game {id}")))?;
+        *used = Instant::now();
+        Ok(game.clone())
+    }
+}
+
+fn check_playing(game: &Game) -> Result<(), Status> {
+    if game.controller.result() != GameResult::Playing {
+        return Err(Status::failed_precondition("the game is over"));
+    }
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl Engine for Service {
+    async fn new_game(
+        &self,
+        request: Request<NewGameRequest>,
+    ) -> Result<Response<NewGameReply>, Status> {
+        let request = request.into_inner();
+        let profile = match request.profile.as_str() {
+            "" => "default",
+            profile => profile,
+        };
+        let params = get_profile(profile)
+            .ok_or_else(|| Status::invalid_argument(format!("unknown profile: {profile}")))?;
+        let rules = Rules { swap: request.swap };
+        let game = Game {
+            controller: GameController::new(rules, params, 0, tt::DEFAULT_MB),
+        };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut games = self.games.lock().unwrap();
+        // those an analysis still streams from aren't idle
+        games.retain(|_, (game, used)| used.elapsed() < IDLE_GAME || Arc::strong_count(game) > 1);
+        games.insert(id, (Arc::new(sync::Mutex::new(game)), Instant::now()));
+        Ok(Response::new(NewGameReply { game_id: id }))
+    }
+
+    async fn end_game(
+        &self,
+        request: Request<EndGameRequest>,
+    ) -> Result<Response<PositionReply>, Status> {
+        let id = request.into_inner().game_id;
+        let game = self.game(id)?;
+        self.games.lock().unwrap().remove(&id);
+        // once the requests still at it are done
+        let position = game.lock().await.position();
+        Ok(Response::new(position))
+    }
+
+    async fn play(&self, request: Request<PlayRequest>) -> Result<Response<PositionReply>, Status> {
+        let request = request.into_inner();
+        let game = self.game(request.game_id)?;
+        let mut game = game.lock().await;
+        check_playing(&game)?;
+        let (state, can_swap) = (game.controller.state(), game.controller.can_swap());
+        let state = parse_move(state, can_swap, &request.r#move).map_err(|error| {
+            Status::invalid_argument(format!("illegal move {}: {error}", request.r#move))
+        })?;
+        game.play(state, None).map_err(Status::invalid_argument)?;
+        Ok(Response::new(game.position()))
+    }
+
+    async fn best_move(
+        &self,
+        request: Request<BestMoveRequest>,
+    ) -> Result<Response<BestMoveReply>, Status> {
+        let request = request.into_inner();
+        let game = self.game(request.game_id)?;
+        let mut game = game.lock().await;
+        check_playing(&game)?;
+        let (score, state, visits) = game.search(request.time_ms as u128).await;
+        let reply = BestMoveReply {
+            best_move: move_name(game.controller.state(), state),
+            evaluation: score,
+            visits: visits.into(),
+        };
+        if request.play {
+            game.play(state, Some(format!("eval {score:.4}")))
+                .map_err(Status::internal)?;
+        }
+        Ok(Response::new(reply))
+    }
+
+    type AnalyzeStream = ReceiverStream<Result<AnalysisInfo, Status>>;
+
+    async fn analyze(
+        &self,
+        request: Request<AnalyzeRequest>,
+    ) -> Result<Response<Self::AnalyzeStream>, Status> {
+        let request = request.into_inner();
+        let game = self.game(request.game_id)?;
+        check_playing(&*game.lock().await)?;
+        let (sender, receiver) = mpsc::channel(16);
+        tokio::spawn(async move {
+            let now = Instant::now();
+            let time = request.time_ms as u128;
+            loop {
+                let left = time.saturating_sub(now.elapsed().as_millis());
+                let info = {
+                    let mut game = game.lock().await;
+                    let interval = (request.interval_ms as u128).clamp(1, left.max(1));
+                    let (score, state, _) = game.search(interval).await;
+                    let controller = &game.controller;
+                    AnalysisInfo {
+                        visits: controller.graph()[controller.root()].nb_visit.into(),
+                        best_move: move_name(controller.state(), state),
+                        evaluation: score,
+                        main_line: controller.pv(),
+                        done: now.elapsed().as_millis() >= time,
+                    }
+                };
+                let done = info.done;
+                // stops when the client went away
+                if sender.send(Ok(info)).await.is_err() || done {
+                    break;
+                }
+            }
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+// `grpc-server ADDR [--hook PROGRAM]`, the hook with the hooks feature
+pub fn main(args: &[String]) {
+    #[cfg(feature = "hooks")]
+    if let Some(at) = args.iter().position(|arg| arg == "--hook") {
+        let program = args
+            .get(at + 1)
+            .unwrap_or_else(|| usage_error("--hook needs a program"));
+        crate::hooks::register(
+            crate::hooks::program(program).unwrap_or_else(|error| usage_error(&error)),
+        );
+    }
+    let addr: SocketAddr = args
+        .first()
+        .and_then(|addr| addr.parse().ok())
+        .unwrap_or_else(|| usage_error("grpc-server needs an address like 127.0.0.1:50051"));
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to start the runtime");
+    println!("serving on {addr}");
+    let served = runtime.block_on(
+        Server::builder()
+            .add_service(EngineServer::new(Service::default()))
+            .serve(addr),
+    );
+    if let Err(error) = served {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
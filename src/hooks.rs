@@ -0,0 +1,145 @@
+// Hooks into the games (with the hooks feature): the functions given to register are called, in
+// the order they came, when a game starts, after each of its moves and once it's over, with all
+// the game has so far, so logging, bots or overlays can be built on the engine without changing
+// it. Every game played through a GameController goes to them: the terminal's, simple's,
+// protocol's, the matches' and the gRPC service's. --hook PROGRAM registers one starting
+// PROGRAM, whose standard input gets every event as a json line:
+//
+//     {"event":"start","tags":{"Date":"2026.10.14","First":"human",...},"moves":[],
+//      "first":0,"second":0}
+//     {"event":"move","tags":{...},"moves":["4"],"first":8,"second":0,"by":"first",
+//      "comment":null,"chances":0.4812,"visits":3021}
+//     {"event":"end","tags":{...,"Result":"1-0"},"moves":["4",...],"first":...,"second":...,
+//      "time":[10452,8210],"stats":{"searches":12,"playouts":40211,...}}
+//
+// (each on one line), first and second being the discs of the first and second player as
+// bitboards (cell y*8+x) and the start's those of the position the game starts from. A move's
+// chances are those of the player who made it as the engine's tree has them (from 0 to 1, null
+// while it knows nothing of the position) and its visits those of the tree below the position;
+// the end has each player's thinking time in milliseconds and what the engine's searches added
+// up to. A program that can't keep up holds the game up; one gone is forgotten.
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::Mutex,
+};
+
+use crate::{board::first_to_move, record::Record, telemetry::json_string, SearchStats};
+
+pub enum Event<'a> {
+    Start {
+        game: &'a Record,
+        state: (u64, u64),
+    },
+    Move {
+        game: &'a Record,
+        // the position after it, the last of the game's moves
+        state: (u64, u64),
+        // for the player who moved
+        chances: Option<f64>,
+        visits: u32,
+    },
+    End {
+        game: &'a Record,
+        state: (u64, u64),
+        // the first and the second player's, milliseconds
+        clocks: [u128; 2],
+        stats: &'a SearchStats,
+    },
+}
+
+pub type Hook = Box<dyn FnMut(&Event) + Send>;
+
+static HOOKS: Mutex<Vec<Hook>> = Mutex::new(Vec::new());
+
+pub fn register(hook: Hook) {
+    HOOKS.lock().unwrap().push(hook);
+}
+
+pub fn emit(event: &Event) {
+    for hook in HOOKS.lock().unwrap().iter_mut() {
+        hook(event);
+    }
+}
+
+// the tags, moves and discs every event has, the position being the one after the moves
+fn game_json(game: &Record, (p1, p2): (u64, u64)) -> String {
+    let tags: Vec<String> = game
+        .tags
+        .iter()
+        .map(|(name, value)| format!("{}:{}", json_string(name), json_string(value)))
+        .collect();
+    let moves: Vec<String> = game
+        .moves
+        .iter()
+        .map(|(text, _)| json_string(text))
+        .collect();
+    let (first, second) = if first_to_move(game.moves.len()) {
+        (p1, p2)
+    } else {
+        (p2, p1)
+    };
+    format!(
+        "\"tags\":{{{}}},\"moves\":[{}],\"first\":{first},\"second\":{second}",
+        tags.join(","),
+        moves.join(",")
+    )
+}
+
+pub fn to_json(event: &Event) -> String {
+    match event {
+        Event::Start { game, state } => {
+            format!("{{\"event\":\"start\",{}}}", game_json(game, *state))
+        }
+        Event::Move {
+            game,
+            state,
+            chances,
+            visits,
+        } => {
+            let comment = match game.moves.last() {
+                Some((_, Some(comment))) => json_string(comment),
+                _ => "null".to_string(),
+            };
+            let chances = chances.map_or("null".to_string(), |chances| format!("{chances:.4}"));
+            format!(
+                "{{\"event\":\"move\",{},\"by\":\"{}\",\"comment\":{comment},\"chances\":{chances},\"visits\":{visits}}}",
+                game_json(game, *state),
+                // the swap counts as a move
+                if game.moves.len() % 2 == 1 { "first" } else { "second" }
+            )
+        }
+        Event::End {
+            game,
+            state,
+            clocks: [first, second],
+            stats,
+        } => {
+            format!(
+                "{{\"event\":\"end\",{},\"time\":[{first},{second}],\"stats\":{{\"searches\":{},\"iterations\":{},\"playouts\":{},\"nodes\":{},\"time\":{},\"depth\":{:.2}}}}}",
+                game_json(game, *state),
+                stats.searches,
+                stats.iterations,
+                stats.playouts,
+                stats.nodes,
+                stats.time,
+                stats.average_depth()
+            )
+        }
+    }
+}
+
+// --hook PROGRAM: the events as json lines on the program's standard input
+pub fn program(path: &str) -> Result<Hook, String> {
+    let mut child = Command::new(path)
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|error| format!("can't start {path}: {error}"))?;
+    Ok(Box::new(move |event| {
+        if let Some(stdin) = &mut child.stdin {
+            if writeln!(stdin, "{}", to_json(event)).is_err() {
+                child.stdin = None;
+            }
+        }
+    }))
+}
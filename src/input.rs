@@ -0,0 +1,49 @@
+// the terminal's lines come through a thread of their own, so that a read can give up when
+// the human's clock runs out
+use std::{
+    io,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError},
+        Mutex, OnceLock,
+    },
+    thread,
+    time::Instant,
+};
+
+pub enum Line {
+    Text(String),
+    TimedOut,
+    // end of input
+    Closed,
+}
+
+static LINES: OnceLock<Mutex<Receiver<String>>> = OnceLock::new();
+
+fn lines() -> &'static Mutex<Receiver<String>> {
+    LINES.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Mutex::new(receiver)
+    })
+}
+
+// the next line without its newline, waiting until the deadline if there's one
+pub fn read_line(deadline: Option<Instant>) -> Line {
+    let lines = lines().lock().unwrap();
+    let line = match deadline {
+        None => lines.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        Some(deadline) => lines.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+    };
+    match line {
+        Ok(line) => Line::Text(line),
+        Err(RecvTimeoutError::Timeout) => Line::TimedOut,
+        Err(RecvTimeoutError::Disconnected) => Line::Closed,
+    }
+}
@@ -0,0 +1,2801 @@
+use std::{
+    env, iter,
+    mem::{self, size_of_val},
+    ops::Range,
+    process,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use board::{
+    get_moves, get_status, is_over, is_winning, parse_column, parse_move, parse_typed_move,
+    play_column, show_grid, MoveError, Symbols, FULL_GRID, SYMBOLS,
+};
+use explain::principal_variation;
+use players::MAX_LEVEL;
+use rules::Scoring;
+use solver::SolverCache;
+
+mod analyze;
+mod annotate;
+mod audit;
+mod batch;
+mod board;
+mod book;
+mod chunks;
+mod compare;
+mod controller;
+mod daily;
+mod dataset;
+mod describe;
+mod dump;
+mod elo;
+mod eval;
+mod explain;
+mod fuzz;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "hooks")]
+pub mod hooks;
+mod input;
+mod live;
+mod matches;
+mod opponents;
+mod players;
+mod positions;
+mod protocol;
+mod puzzles;
+mod random;
+mod record;
+mod rules;
+mod scale;
+mod search;
+mod solver;
+mod spinner;
+mod storage;
+mod suite;
+mod telemetry;
+mod theme;
+mod timing;
+mod tt;
+
+// what other crates build on: games against the engine, driven by a front-end of theirs, and
+// what its searches know
+pub use board::STATUS;
+pub use controller::{ChildStats, EngineMove, GameController, GameResult};
+pub use record::Record;
+
+const TIME_PER_MOVE: u128 = 1000; // milliseconds
+const DEFAULT_MAX_MEMORY: usize = 1024; // megabytes
+const MEMORY_WARNING: f64 = 0.9; // of the cap
+const COACH_TIME: u128 = 300; // milliseconds
+const LONG_SEARCH: u128 = 3000; // milliseconds, from which searches report on the way
+const REPORT_EVERY: u64 = 200_000; // iterations
+const PROGRESS_EVERY: u64 = 1000; // iterations, between two looks at the root's moves
+
+// --deterministic, unless --seed or --iterations say otherwise
+const DETERMINISTIC_SEED: u64 = 0;
+const DETERMINISTIC_ITERATIONS: u64 = 10_000;
+// scores closer than this are tied and Params::tie_break settles them, so rounding
+// differences between platforms don't change the choices
+const TIE: f64 = 1e-12;
+
+#[derive(Clone, Copy)]
+pub struct Rules {
+    // after the first move the second player may take it over instead of replying
+    pub swap: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Playout {
+    Random,
+    // takes immediate wins and blocks immediate losses, otherwise random
+    Greedy,
+    // batch::BATCH random games at once, counted as that many visits
+    Batch,
+}
+
+// what happens when the human's time for a move runs out
+#[derive(Clone, Copy, PartialEq)]
+enum FlagFall {
+    Forfeit,
+    Random,
+}
+
+const FLAG_FALLS: [(&str, FlagFall); 2] =
+    [("forfeit", FlagFall::Forfeit), ("random", FlagFall::Random)];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Selection {
+    Ucb1,
+    // the exploration term shrinks as 1/n instead of 1/sqrt(n)
+    Puct,
+}
+
+// which of equally good moves the selection and the final choice take
+#[derive(Clone, Copy, PartialEq)]
+enum TieBreak {
+    // the central columns first (as move_prior sees them), then the lower ones
+    Center,
+    // the first child, in the order they were created
+    Order,
+}
+
+// what a playout cut short by Params::max_playout is worth
+#[derive(Clone, Copy, PartialEq)]
+enum Cutoff {
+    // a draw, contempt included
+    Draw,
+    // the static evaluation of where it stopped, from a loss to a win
+    Eval,
+}
+
+const CUTOFFS: [(&str, Cutoff); 2] = [("draw", Cutoff::Draw), ("eval", Cutoff::Eval)];
+const TIE_BREAKS: [(&str, TieBreak); 2] =
+    [("center", TieBreak::Center), ("order", TieBreak::Order)];
+const SELECTIONS: [(&str, Selection); 2] = [("ucb1", Selection::Ucb1), ("puct", Selection::Puct)];
+const PLAYOUTS: [(&str, Playout); 3] = [
+    ("random", Playout::Random),
+    ("greedy", Playout::Greedy),
+    ("batch", Playout::Batch),
+];
+
+// what makes the bot's style, scores go from 0 (loss) to 2 (win)
+#[derive(Clone, Copy)]
+pub struct Params {
+    selection: Selection,
+    uctc: f64,
+    // how much less than 1 a draw is worth to the bot
+    contempt: f64,
+    // 0 always plays the best move, higher values pick among the good ones at random
+    temperature: f64,
+    playout: Playout,
+    backpropagation: &'static dyn Backpropagation,
+    // how much quicker wins (and slower losses) are preferred, from 0 to 1
+    shaping: f64,
+    // stops the search early, whatever time is left
+    max_iterations: Option<u64>,
+    // progressive widening: a node with n visits only opens its 1 + n^widening most promising
+    // children (by move_prior), 0 opens them all at once
+    widening: f64,
+    // progressive bias: how much the static evaluation counts while a child has few visits
+    bias: f64,
+    // positions with this few empty cells are solved exactly instead of played out, 0 never,
+    // nor are immediate wins and losses looked for before that
+    solver_empty: u32,
+    tie_break: TieBreak,
+    // the plies a playout goes on for at most before `cutoff` scores it, 0 to the end, for the
+    // variants whose games last
+    max_playout: u32,
+    cutoff: Cutoff,
+    // playouts end with the solver's result once solver_empty cells are left, under the
+    // standard rules
+    tablebase_playouts: bool,
+}
+
+const PROFILES: [(&str, Params); 5] = [
+    (
+        "default",
+        Params {
+            selection: Selection::Ucb1,
+            uctc: 2.0,
+            contempt: 0.0,
+            temperature: 0.0,
+            playout: Playout::Random,
+            backpropagation: &SolverBackpropagation,
+            shaping: 0.0,
+            max_iterations: None,
+            widening: 0.0,
+            bias: 0.0,
+            solver_empty: 12,
+            tie_break: TieBreak::Center,
+            max_playout: 0,
+            cutoff: Cutoff::Draw,
+            tablebase_playouts: false,
+        },
+    ),
+    (
+        // avoids draws and goes for the quickest kill
+        "aggressive",
+        Params {
+            selection: Selection::Ucb1,
+            uctc: 2.0,
+            contempt: 0.3,
+            temperature: 0.0,
+            playout: Playout::Greedy,
+            backpropagation: &SolverBackpropagation,
+            shaping: 0.5,
+            max_iterations: None,
+            widening: 0.0,
+            bias: 0.2,
+            solver_empty: 12,
+            tie_break: TieBreak::Center,
+            max_playout: 0,
+            cutoff: Cutoff::Draw,
+            tablebase_playouts: false,
+        },
+    ),
+    (
+        // expects careful replies and is happy with a draw
+        "solid",
+        Params {
+            selection: Selection::Ucb1,
+            uctc: 1.5,
+            contempt: -0.2,
+            temperature: 0.0,
+            playout: Playout::Greedy,
+            backpropagation: &SolverBackpropagation,
+            shaping: 0.0,
+            max_iterations: None,
+            widening: 0.0,
+            bias: 0.2,
+            solver_empty: 12,
+            tie_break: TieBreak::Center,
+            max_playout: 0,
+            cutoff: Cutoff::Draw,
+            tablebase_playouts: false,
+        },
+    ),
+    (
+        // random playouts reward lines where a careless opponent goes wrong
+        "trappy",
+        Params {
+            selection: Selection::Ucb1,
+            uctc: 2.0,
+            contempt: 0.2,
+            temperature: 0.05,
+            playout: Playout::Random,
+            backpropagation: &SolverBackpropagation,
+            shaping: 0.2,
+            max_iterations: None,
+            widening: 0.0,
+            bias: 0.0,
+            solver_empty: 12,
+            tie_break: TieBreak::Center,
+            max_playout: 0,
+            cutoff: Cutoff::Draw,
+            tablebase_playouts: false,
+        },
+    ),
+    (
+        // for small devices: nothing worked out beside the playouts, and the `fast` profile
+        // also caps the tree and the tables (see parse_options)
+        FAST_PROFILE,
+        Params {
+            selection: Selection::Ucb1,
+            uctc: 2.0,
+            contempt: 0.0,
+            temperature: 0.0,
+            playout: Playout::Random,
+            backpropagation: &SolverBackpropagation,
+            shaping: 0.0,
+            max_iterations: None,
+            widening: 0.0,
+            bias: 0.0,
+            solver_empty: 10,
+            tie_break: TieBreak::Center,
+            max_playout: 0,
+            cutoff: Cutoff::Draw,
+            tablebase_playouts: false,
+        },
+    ),
+];
+
+const FAST_PROFILE: &str = "fast";
+// the fast profile's caps, megabytes
+const FAST_TT_MB: usize = 2;
+const FAST_MAX_MEMORY: usize = 64;
+
+// weaker levels search less and pick their moves more loosely, the top one is the plain profile
+fn adapt_to_level(params: Params, level: u32) -> Params {
+    if level >= MAX_LEVEL {
+        return params;
+    }
+    Params {
+        max_iterations: Some(25 << (level - 1)),
+        temperature: params.temperature.max(0.05 * (MAX_LEVEL - level) as f64),
+        // a perfect endgame would give the weaker levels away
+        solver_empty: 0,
+        ..params
+    }
+}
+
+pub fn get_profile(name: &str) -> Option<Params> {
+    PROFILES
+        .iter()
+        .find(|(profile, _)| *profile == name)
+        .map(|(_, params)| *params)
+}
+
+// the options `set` (and the protocol's setoption) can change between two searches, the tree
+// staying as it is
+const SETTINGS: [&str; 3] = ["exploration", "time", "playout"];
+
+fn set_option(params: &mut Params, time: &mut u128, name: &str, value: &str) -> Result<(), String> {
+    let invalid = || format!("invalid {name}: {value}");
+    match name {
+        "exploration" => {
+            params.uctc = value
+                .parse()
+                .ok()
+                .filter(|uctc: &f64| uctc.is_finite() && *uctc >= 0.0)
+                .ok_or_else(invalid)?
+        }
+        "time" => {
+            *time = value
+                .parse()
+                .ok()
+                .filter(|time| *time > 0)
+                .ok_or_else(invalid)?
+        }
+        "playout" => {
+            params.playout = PLAYOUTS
+                .iter()
+                .find(|(playout, _)| *playout == value)
+                .map(|(_, playout)| *playout)
+                .ok_or_else(invalid)?
+        }
+        _ => {
+            return Err(format!(
+                "unknown option: {name}, one of {}",
+                SETTINGS.join(", ")
+            ))
+        }
+    }
+    Ok(())
+}
+
+// the current values, as `set` takes them
+fn settings(params: &Params, time: u128) -> String {
+    let playout = PLAYOUTS
+        .iter()
+        .find(|(_, playout)| *playout == params.playout)
+        .map_or("?", |(name, _)| name);
+    format!("exploration {} time {time} playout {playout}", params.uctc)
+}
+
+// what carries over from one game to the next
+struct Session {
+    // the tree of the position the last game started from, and its root
+    tree: Option<(Vec<Node>, usize)>,
+    // what `set` changed, in order, for the next games too
+    settings: Vec<(String, String)>,
+    cache: SolverCache,
+    // the human's wins, losses and draws
+    results: [u32; 3],
+}
+
+impl Session {
+    fn new(tt_mb: usize) -> Session {
+        Session {
+            tree: None,
+            settings: vec![],
+            cache: SolverCache::new(tt_mb),
+            results: [0; 3],
+        }
+    }
+}
+
+struct Options {
+    rules: Rules,
+    profile: String,
+    params: Params,
+    // handicaps, to give beginners a chance
+    random_moves: u32,
+    extra_disc: Option<u32>,
+    time_handicap: u128,
+    explain: bool,
+    coach: bool,
+    player: Option<String>,
+    players_file: String,
+    adaptive: bool,
+    // ponders on the reply the player's past games make the likeliest, see players.rs
+    model_opponent: bool,
+    // size of the solver's transposition table
+    tt_mb: usize,
+    // a warning is printed when the tree and the tables get close to it
+    max_memory_mb: usize,
+    // the file the game is appended to
+    record: Option<String>,
+    // a second look at every move once the game is over
+    annotate: bool,
+    // the json lines file the game's summary is appended to
+    telemetry: Option<String>,
+    // a built-in player in place of the engine, see opponents::by_name
+    opponent: Option<String>,
+    // the human's time per move, milliseconds
+    human_time: Option<u128>,
+    // how the bot's time changes over the game, see timing.rs
+    time_curve: timing::Curve,
+    flag_fall: FlagFall,
+    // every game of a session starts from nothing, as the first one did
+    forget: bool,
+    theme: theme::Theme,
+    // the human's, whichever seat they take
+    my_symbol: Option<char>,
+    // the human types columns 0 -> 6
+    zero_based: bool,
+    // sentences instead of the grid
+    describe: bool,
+    // a live chart of the bot's moves while it thinks, see live.rs
+    live: bool,
+    // every game (and protocol newgame) starts the generator from it
+    seed: Option<u64>,
+    // searches stop after that many iterations rather than on the clock
+    iterations: Option<u64>,
+    // the opening book the searches start from and learn into
+    book: Option<String>,
+    variant: rules::Variant,
+    // the positions given from outside are checked (see board::check_position)
+    strict: bool,
+    // the fast profile: capped memory, no pondering, one thread and fewer lines printed
+    fast: bool,
+    // the workers of the bot's searches, see grow_parallel
+    threads: usize,
+}
+
+const USAGE: &str =
+    "usage: mcst_connect4 [--swap] [--random-moves N] [--extra-disc COLUMN] [--time-handicap MS]
+                     [--profile default|aggressive|solid|trappy|fast] [--explain] [--coach]
+                     [--player NAME] [--players-file FILE] [--adaptive] [--model-opponent]
+                     [--tt-mb MB] [--max-memory MB] [--backprop solver|discounted]
+                     [--playout random|greedy|batch] [--widening EXPONENT]
+                     [--bias WEIGHT] [--record FILE] [--annotate] [--telemetry FILE]
+                     [--human-time MS] [--on-flag forfeit|random] [--opponent PLAYER]
+                     [--forget] [--symbols FIRST_SECOND] [--me SYMBOL] [--zero-based]
+                     [--describe] [--live] [--theme plain|high-contrast|emoji|FILE]
+                     [--deterministic] [--seed N] [--iterations N] [--book FILE]
+                     [--scoring first-four|most-fours] [--full-board draw|first|second]
+                     [--tie-break center|order] [--max-playout PLIES]
+                     [--cutoff draw|eval] [--tablebase-playouts] [--time-curve FILE]
+                     [--threads N] [--strict] [--hook PROGRAM] (with the hooks feature)
+       mcst_connect4 puzzles generate [COUNT] [FILE]
+       mcst_connect4 puzzles play [FILE]
+       mcst_connect4 inspect-tree FILE [--strict]
+       mcst_connect4 fuzz [GAMES]
+       mcst_connect4 compare-policies [ITERATIONS] [SEED]
+       mcst_connect4 bench-storage [ITERATIONS] [SEED]
+       mcst_connect4 uci-dataset FILE [SEARCHED] [ITERATIONS]
+       mcst_connect4 protocol [OPTIONS]
+       mcst_connect4 simple [OPTIONS]
+       mcst_connect4 connect ADDR [OPTIONS]
+       mcst_connect4 match-host ADDR [--local PLAYER] [MATCH OPTIONS]
+       mcst_connect4 self-play PLAYER PLAYER [MATCH OPTIONS]
+       mcst_connect4 round-robin PLAYER PLAYER... [MATCH OPTIONS]
+       mcst_connect4 show-record FILE [GAME]
+       mcst_connect4 analyze [MOVES] [MS] [--searchmoves COLUMNS | --exclude COLUMNS]
+       mcst_connect4 analyze-file FILE [MS] [--json] [--output FILE]
+       mcst_connect4 annotate FILE [GAME] [MS]
+       mcst_connect4 audit FILE [MS] [PROFILE]
+       mcst_connect4 estimate-elo [GAMES] [MS] [PROFILE]
+       mcst_connect4 daily [OPTIONS]
+       mcst_connect4 --version [OPTIONS]
+       mcst_connect4 grpc-server ADDR [--hook PROGRAM] (with the grpc feature)
+
+MATCH OPTIONS: [--games N] [--time MS] [--swap] [--adjudicate]
+               [--openings FILE | --random-openings PLIES] [--record FILE] [--book FILE]
+               [--hook PROGRAM] (with the hooks feature)
+PLAYER: random, greedy, minimax:DEPTH, mcts:ITERATIONS, a profile (with settings, as in
+        default,exploration=0.9) or vote:PROFILE+PROFILE";
+
+#[derive(Clone)]
+struct Node {
+    state: (u64, u64),
+    score: f32,
+    nb_visit: u32,
+    parent: Option<u32>,
+    // the children are created together, so they sit next to each other in the arena
+    first_child: u32,
+    nb_children: u8,
+    status: STATUS,
+    can_swap: bool,
+    // whether the player to move is the first one, which a full board's result can depend on
+    // (see board::first_to_move)
+    first: bool,
+    // eval::evaluate(), only worked out when the search uses it
+    evaluation: f32,
+}
+
+impl Node {
+    fn new(
+        state: (u64, u64),
+        parent: Option<usize>,
+        status: STATUS,
+        can_swap: bool,
+        first: bool,
+    ) -> Node {
+        Node {
+            state,
+            score: 0.0,
+            nb_visit: 0,
+            parent: parent.map(|parent| parent as u32),
+            first_child: 0,
+            nb_children: 0,
+            status,
+            can_swap,
+            first,
+            evaluation: 0.0,
+        }
+    }
+
+    fn children(&self) -> Range<usize> {
+        let first = self.first_child as usize;
+        first..first + self.nb_children as usize
+    }
+
+    fn parent(&self) -> Option<usize> {
+        self.parent.map(|parent| parent as usize)
+    }
+
+    // for the player who moved into it, nothing known yet counts as a draw
+    fn mean(&self) -> f64 {
+        if self.nb_visit == 0 {
+            return match self.status {
+                STATUS::LOST => 2.0,
+                STATUS::WON => 0.0,
+                _ => 1.0,
+            };
+        }
+        self.score as f64 / self.nb_visit as f64
+    }
+}
+
+fn get_node_moves(node: &Node) -> Vec<(u64, u64)> {
+    let mut moves = get_moves(node.state.0, node.state.1);
+    if node.can_swap {
+        // the discs stay where they are, only the players change sides
+        moves.push(node.state);
+    }
+    moves
+}
+
+fn selection_value(child: &Node, parent_visits: u32, params: &Params) -> f64 {
+    let visits = child.nb_visit as f64;
+    // the bias fades as the visits come, the limit stays the same
+    let mean = child.mean() + params.bias * child.evaluation as f64 / (1.0 + visits);
+    match params.selection {
+        Selection::Ucb1 => mean + params.uctc * ((parent_visits as f64).log2() / visits).sqrt(),
+        // every move gets the same prior
+        Selection::Puct => mean + params.uctc * (parent_visits as f64).sqrt() / (1.0 + visits),
+    }
+}
+
+// `excluded` being the moves left out at this node, the root
+fn selection(
+    node: usize,
+    graph: &mut Vec<Node>,
+    rules: Rules,
+    params: &Params,
+    cache: &mut SolverCache,
+    excluded: &[(u64, u64)],
+) -> usize {
+    if graph[node].status != STATUS::PLAYING {
+        return node;
+    }
+    if graph[node].nb_children == 0 {
+        let moves = get_node_moves(&graph[node]);
+        if moves.is_empty() {
+            return node;
+        }
+        expansion(node, graph, moves, rules, params, cache);
+    }
+    let open = open_children(&graph[node], params);
+    // each child gets a first playout before the statistics take over
+    if let Some(child) = graph[node]
+        .children()
+        .take(open)
+        .filter(|child| graph[*child].nb_visit == 0 && !excluded.contains(&graph[*child].state))
+        .reduce(
+            |best, child| match wins_tie(graph, node, child, best, params) {
+                true => child,
+                false => best,
+            },
+        )
+    {
+        return child;
+    }
+    // eval each children and take the best one
+    let mut best = None;
+    let proven = params.backpropagation.statistics().proven;
+    for child in graph[node].children().take(open) {
+        if proven && graph[child].status == STATUS::WON {
+            // proven to lose for us, no point in looking at it again
+            continue;
+        }
+        if excluded.contains(&graph[child].state) {
+            continue;
+        }
+        let value = selection_value(&graph[child], graph[node].nb_visit, params);
+        if improves(graph, node, child, value, best, params) {
+            best = Some((value, child));
+        }
+    }
+    match best {
+        Some((_, child)) => selection(child, graph, rules, params, cache, &[]),
+        // every open child is a proven loss, the next one gets its chance
+        None if open < graph[node].nb_children as usize => {
+            graph[node].children().nth(open).unwrap()
+        }
+        None => node,
+    }
+}
+
+// whether `child`, as good as `best` to within TIE, is taken over it
+fn wins_tie(graph: &[Node], parent: usize, child: usize, best: usize, params: &Params) -> bool {
+    match params.tie_break {
+        TieBreak::Order => child < best,
+        TieBreak::Center => {
+            let state = graph[parent].state;
+            // the swap comes after the columns
+            let column = |x: usize| match graph[x].state {
+                next if next == state => 7,
+                next => (next.1 ^ state.0).trailing_zeros() % 8,
+            };
+            let prior = |x: usize| move_prior(state, graph[x].state);
+            prior(child)
+                .total_cmp(&prior(best))
+                .then(column(best).cmp(&column(child)))
+                .is_gt()
+        }
+    }
+}
+
+// whether `value` makes `child` the best of the parent's children so far
+fn improves(
+    graph: &[Node],
+    parent: usize,
+    child: usize,
+    value: f64,
+    best: Option<(f64, usize)>,
+    params: &Params,
+) -> bool {
+    let Some((best_value, best)) = best else {
+        return true;
+    };
+    value > best_value + TIE
+        || (value >= best_value - TIE && wins_tie(graph, parent, child, best, params))
+}
+
+fn open_children(node: &Node, params: &Params) -> usize {
+    let nb_children = node.nb_children as usize;
+    if params.widening == 0.0 {
+        return nb_children;
+    }
+    nb_children.min(1 + (node.nb_visit as f64).powf(params.widening) as usize)
+}
+
+// how promising a move looks before any search: central columns first, the swap in between
+fn move_prior(state: (u64, u64), child: (u64, u64)) -> f64 {
+    if child == state {
+        return 0.5;
+    }
+    let column = (child.1 ^ state.0).trailing_zeros() % 8;
+    1.0 - (column as f64 - 3.0).abs() / 3.0
+}
+
+// creates every child at once, at the end of the arena
+fn expansion(
+    node: usize,
+    graph: &mut Vec<Node>,
+    mut moves: Vec<(u64, u64)>,
+    rules: Rules,
+    params: &Params,
+    cache: &mut SolverCache,
+) {
+    if params.widening > 0.0 {
+        // the children open in this order
+        let state = graph[node].state;
+        moves.sort_by(|a, b| move_prior(state, *b).total_cmp(&move_prior(state, *a)));
+    }
+    graph[node].first_child = graph.len() as u32;
+    graph[node].nb_children = moves.len() as u8;
+    let can_swap = rules.swap && graph[node].state == (0, 0);
+    let first = !graph[node].first;
+    // the solver only knows the standard rules
+    let solver_empty = if rules::is_standard() {
+        params.solver_empty
+    } else {
+        0
+    };
+    for child_move in &moves {
+        let mut status = get_status(child_move.0, child_move.1, first);
+        let empty = 42 - (child_move.0 | child_move.1).count_ones();
+        if status == STATUS::PLAYING && empty <= solver_empty {
+            status = solver::status(solver::solve(child_move.0, child_move.1, cache));
+        } else if status == STATUS::PLAYING && solver_empty > 0 {
+            // a playout would only find out the same, less surely
+            status = solver::immediate_status(child_move.0, child_move.1);
+        }
+        let mut child = Node::new(*child_move, Some(node), status, can_swap, first);
+        if params.bias > 0.0 {
+            child.evaluation = eval::evaluate(child_move.0, child_move.1) as f32;
+        }
+        graph.push(child);
+    }
+}
+
+fn playout_move(p1: u64, p2: u64, playout: Playout) -> (u64, u64) {
+    let moves = get_moves(p1, p2);
+    if playout == Playout::Greedy {
+        if let Some(winning) = moves.iter().find(|(_, player)| is_winning(*player)) {
+            return *winning;
+        }
+        // the opponent (p2) would win on the disc we just placed
+        if let Some(blocking) = moves
+            .iter()
+            .find(|(_, player)| is_winning(p2 | (player ^ p1)))
+        {
+            return *blocking;
+        }
+    }
+    moves[random::below(moves.len())]
+}
+
+// plays the game out randomly, the result is for the player who just moved (p2),
+// `draw` being what a draw is worth to them and `first` whether p1 is the first player
+fn simulation(
+    mut p1: u64,
+    mut p2: u64,
+    first: bool,
+    draw: f64,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> f64 {
+    // only the standard scoring ends with the first four
+    let first_four = rules::current().scoring == Scoring::FirstFour;
+    let tablebase = params.tablebase_playouts && rules::is_standard();
+    let mut plies = 0;
+    loop {
+        let empty = 42 - (p1 | p2).count_ones();
+        // the plies the game still lasts, played or proven
+        let mut left = 0;
+        let status = if first_four && is_winning(p2) {
+            STATUS::LOST
+        } else if p1 | p2 == FULL_GRID {
+            rules::full_board(p1, p2, first == (plies % 2 == 0))
+        } else if tablebase && empty <= params.solver_empty {
+            let score = solver::solve(p1, p2, cache);
+            // the score is the number of empty cells left when it ends, plus one
+            left = (empty + 1).saturating_sub(score.unsigned_abs());
+            solver::status(score)
+        } else {
+            STATUS::PLAYING
+        };
+        match status {
+            STATUS::PLAYING => {}
+            STATUS::DRAW => return draw,
+            status => {
+                let win = 2.0 - params.shaping * (plies + left) as f64 / 42.0;
+                // for whoever moved last (p2)
+                let result = if status == STATUS::LOST {
+                    win
+                } else {
+                    2.0 - win
+                };
+                return if plies % 2 == 0 { result } else { 2.0 - result };
+            }
+        }
+        if plies == params.max_playout && plies > 0 {
+            let result = match params.cutoff {
+                Cutoff::Draw => return draw,
+                // for whoever moved last (p2)
+                Cutoff::Eval => 1.0 + eval::evaluate(p1, p2),
+            };
+            return if plies % 2 == 0 { result } else { 2.0 - result };
+        }
+        (p1, p2) = playout_move(p1, p2, params.playout);
+        plies += 1;
+    }
+}
+
+// a position is won as soon as one move wins,
+// lost or drawn once every move is known to be so
+fn update_status(node: usize, graph: &mut [Node]) {
+    if graph[node].status != STATUS::PLAYING {
+        return;
+    }
+    if graph[node].nb_children == 0 {
+        return;
+    }
+    let mut children = graph[node].children();
+    if children.any(|x| graph[x].status == STATUS::LOST) {
+        graph[node].status = STATUS::WON;
+        return;
+    }
+    let mut children = graph[node].children();
+    if children.all(|x| graph[x].status == STATUS::WON) {
+        graph[node].status = STATUS::LOST;
+    } else if graph[node]
+        .children()
+        .all(|x| graph[x].status != STATUS::PLAYING)
+    {
+        graph[node].status = STATUS::DRAW;
+    }
+}
+
+// the children that aren't proven losing moves
+fn viable_children(graph: &[Node], node: usize) -> Vec<usize> {
+    graph[node]
+        .children()
+        .filter(|x| graph[*x].status != STATUS::WON)
+        .collect()
+}
+
+fn is_only_move(graph: &[Node], node: usize) -> bool {
+    let nb_moves = get_node_moves(&graph[node]).len();
+    nb_moves > 1
+        && graph[node].nb_children as usize == nb_moves
+        && viable_children(graph, node).len() == 1
+}
+
+// what the nodes carry besides their visits and total score, for the selection to rely on
+#[derive(Clone, Copy)]
+struct Statistics {
+    // statuses are propagated up from the proven children
+    proven: bool,
+}
+
+// how a playout result makes its way back up the tree
+trait Backpropagation: Sync {
+    // records the total score of some playouts (for the player who moved into the node),
+    // returns the parent's one
+    fn update(&self, graph: &mut [Node], node: usize, score: f64, playouts: u32) -> f64;
+    fn statistics(&self) -> Statistics;
+}
+
+// plain averages, with the proven results of the mcts-solver
+struct SolverBackpropagation;
+
+impl Backpropagation for SolverBackpropagation {
+    fn update(&self, graph: &mut [Node], node: usize, score: f64, playouts: u32) -> f64 {
+        graph[node].nb_visit += playouts;
+        graph[node].score += score as f32;
+        update_status(node, graph);
+        2.0 * playouts as f64 - score
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics { proven: true }
+    }
+}
+
+// each ply up brings the result closer to a draw, distant outcomes count less
+struct DiscountedBackpropagation {
+    factor: f64,
+}
+
+impl Backpropagation for DiscountedBackpropagation {
+    fn update(&self, graph: &mut [Node], node: usize, score: f64, playouts: u32) -> f64 {
+        graph[node].nb_visit += playouts;
+        graph[node].score += score as f32;
+        let playouts = playouts as f64;
+        playouts - self.factor * (score - playouts)
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics { proven: false }
+    }
+}
+
+const BACKPROPAGATIONS: [(&str, &dyn Backpropagation); 2] = [
+    ("solver", &SolverBackpropagation),
+    ("discounted", &DiscountedBackpropagation { factor: 0.98 }),
+];
+
+fn backpropagation(
+    mut node: usize,
+    graph: &mut [Node],
+    mut score: f64,
+    playouts: u32,
+    params: &Params,
+) {
+    loop {
+        score = params.backpropagation.update(graph, node, score, playouts);
+        match graph[node].parent() {
+            Some(parent) => node = parent,
+            None => return,
+        }
+    }
+}
+
+fn depth(mut node: usize, root: usize, graph: &[Node]) -> u32 {
+    let mut depth = 0;
+    while node != root {
+        node = graph[node].parent().unwrap();
+        depth += 1;
+    }
+    depth
+}
+
+fn tree_memory(graph: &[Node]) -> usize {
+    size_of_val(graph)
+}
+
+fn megabytes(bytes: usize) -> f64 {
+    bytes as f64 / (1 << 20) as f64
+}
+
+// the nodes a tree can have beside the tables, for the fast profile's cap
+fn node_cap(max_memory_mb: usize, cache: &SolverCache) -> usize {
+    let bytes = (max_memory_mb << 20).saturating_sub(cache.memory());
+    bytes / size_of::<Node>()
+}
+
+fn report_memory(graph: &[Node], cache: &SolverCache, max_memory_mb: usize) {
+    let tree = tree_memory(graph);
+    let total = tree + cache.memory();
+    println!(
+        "memory: {:.1} MB ({} nodes) + {:.1} MB transposition table, {:.1} of {max_memory_mb} MB",
+        megabytes(tree),
+        graph.len(),
+        megabytes(cache.memory()),
+        megabytes(total)
+    );
+    let used = megabytes(total) / max_memory_mb as f64;
+    if used >= MEMORY_WARNING {
+        println!(
+            "warning: memory use is at {:.0}% of the {max_memory_mb} MB cap (--max-memory)",
+            100.0 * used
+        );
+    }
+}
+
+// where a long search stands, every REPORT_EVERY iterations
+struct Report {
+    iterations: u64,
+    // milliseconds
+    time: u128,
+    visits: u32,
+    best: (u64, u64),
+    score: f64,
+}
+
+type Reporter = Arc<dyn Fn(&Report) + Send + Sync>;
+
+// what searches added up to, over a game
+#[derive(Clone, Copy, Default)]
+pub struct SearchStats {
+    pub searches: u32,
+    pub iterations: u64,
+    // batch playouts count each of their games
+    pub playouts: u64,
+    // the nodes the searches added to the trees
+    pub nodes: u64,
+    // milliseconds
+    pub time: u128,
+    // of every position played out, from the searched one
+    pub depth: u64,
+}
+
+impl SearchStats {
+    pub fn average_depth(&self) -> f64 {
+        self.depth as f64 / self.iterations.max(1) as f64
+    }
+
+    // for the record's Engine tag
+    fn tag(&self) -> String {
+        format!(
+            "searches {} playouts {} nodes {} time {} depth {:.2}",
+            self.searches,
+            self.playouts,
+            self.nodes,
+            self.time,
+            self.average_depth()
+        )
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} searches, {} playouts ({:.0} a second), {} nodes, {:.1}s, average depth {:.1}",
+            self.searches,
+            self.playouts,
+            self.playouts as f64 * 1000.0 / self.time.max(1) as f64,
+            self.nodes,
+            self.time as f64 / 1000.0,
+            self.average_depth()
+        )
+    }
+}
+
+// when a search stops, besides Params::max_iterations, and what it leaves out
+#[derive(Clone, Default)]
+struct Limits {
+    // milliseconds
+    time: u128,
+    // raised from another thread to stop the search early
+    stop: Option<Arc<AtomicBool>>,
+    report: Option<Reporter>,
+    // root moves neither searched nor played, the root's status aside
+    excluded: Vec<(u64, u64)>,
+    // where the search adds what it did
+    stats: Option<Arc<Mutex<SearchStats>>>,
+    // the search stops once the tree has that many nodes
+    max_nodes: Option<usize>,
+    // where the search keeps the statistics of the root's moves up to date, see search.rs
+    progress: Option<search::Progress>,
+    // the workers of a root-parallel search, one and none alike searching on this thread
+    threads: usize,
+}
+
+impl Limits {
+    fn movetime(time: u128) -> Limits {
+        Limits {
+            time,
+            ..Limits::default()
+        }
+    }
+
+    // short searches are over before anyone could read the reports
+    fn with_reports(self, report: Reporter) -> Limits {
+        if self.time < LONG_SEARCH {
+            return self;
+        }
+        Limits {
+            report: Some(report),
+            ..self
+        }
+    }
+
+    fn excluding(self, excluded: Vec<(u64, u64)>) -> Limits {
+        Limits { excluded, ..self }
+    }
+
+    fn with_stats(self, stats: Arc<Mutex<SearchStats>>) -> Limits {
+        Limits {
+            stats: Some(stats),
+            ..self
+        }
+    }
+
+    fn capped(self, max_nodes: Option<usize>) -> Limits {
+        Limits { max_nodes, ..self }
+    }
+
+    fn with_threads(self, threads: usize) -> Limits {
+        Limits { threads, ..self }
+    }
+
+    fn with_progress(self, progress: search::Progress) -> Limits {
+        Limits {
+            progress: Some(progress),
+            ..self
+        }
+    }
+
+    fn reached(&self, start: Instant) -> bool {
+        start.elapsed().as_millis() >= self.time
+            || self
+                .stop
+                .as_ref()
+                .is_some_and(|stop| stop.load(Ordering::Relaxed))
+    }
+}
+
+// the move the search would settle on so far, temperature aside
+fn current_best(
+    graph: &[Node],
+    root: usize,
+    excluded: &[(u64, u64)],
+    params: &Params,
+) -> Option<usize> {
+    let mut best = None;
+    for x in graph[root].children() {
+        if graph[x].nb_visit == 0 || excluded.contains(&graph[x].state) {
+            continue;
+        }
+        // ties broken as the final choice would
+        if improves(graph, root, x, graph[x].mean(), best, params) {
+            best = Some((graph[x].mean(), x));
+        }
+    }
+    best.map(|(_, x)| x)
+}
+
+// the iterations of a search, until the limits or a proof stop it
+fn grow(
+    mut graph: Vec<Node>,
+    root: usize,
+    limits: &Limits,
+    rules: Rules,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Vec<Node> {
+    let now = Instant::now();
+    let nodes = graph.len();
+    let mut iterations = 0;
+    let mut total_playouts = 0;
+    let mut total_depth = 0;
+    // at least one iteration, so the root always has a child to pick
+    loop {
+        let node = selection(root, &mut graph, rules, params, cache, &limits.excluded);
+        let node_depth = depth(node, root, &graph);
+        // the contempt is the bot's, the other player sees its mirror image
+        let draw = if node_depth % 2 == 1 {
+            1.0 - params.contempt
+        } else {
+            1.0 + params.contempt
+        };
+        // proven positions don't need a playout, the score is for the player who moved into it
+        let (p1, p2) = graph[node].state;
+        let (score, playouts) = match graph[node].status {
+            // the batches only know the standard rules
+            STATUS::PLAYING if params.playout == Playout::Batch && rules::is_standard() => {
+                (batch::simulation(p1, p2, draw, params), batch::BATCH as u32)
+            }
+            STATUS::PLAYING => {
+                let first = graph[node].first;
+                (simulation(p1, p2, first, draw, params, cache), 1)
+            }
+            STATUS::LOST => (2.0, 1),
+            STATUS::WON => (0.0, 1),
+            STATUS::DRAW => (draw, 1),
+        };
+        backpropagation(node, &mut graph, score, playouts, params);
+        iterations += 1;
+        total_playouts += playouts as u64;
+        total_depth += node_depth as u64;
+        if let Some(report) = &limits.report {
+            if iterations % REPORT_EVERY == 0 {
+                if let Some(best) = current_best(&graph, root, &limits.excluded, params) {
+                    report(&Report {
+                        iterations,
+                        time: now.elapsed().as_millis(),
+                        visits: graph[root].nb_visit,
+                        best: graph[best].state,
+                        score: graph[best].mean(),
+                    });
+                }
+            }
+        }
+        if let Some(progress) = &limits.progress {
+            if iterations % PROGRESS_EVERY == 1 || graph[root].status != STATUS::PLAYING {
+                *progress.lock().unwrap() = search::root_children(&graph, root);
+            }
+        }
+        // nothing left to find out, whatever the time left
+        if graph[root].status != STATUS::PLAYING {
+            break;
+        }
+        if limits.reached(now) || params.max_iterations.is_some_and(|max| iterations >= max) {
+            break;
+        }
+        if limits.max_nodes.is_some_and(|max| graph.len() >= max) {
+            break;
+        }
+    }
+    if let Some(stats) = &limits.stats {
+        let mut stats = stats.lock().unwrap();
+        stats.searches += 1;
+        stats.iterations += iterations;
+        stats.playouts += total_playouts;
+        stats.nodes += (graph.len() - nodes) as u64;
+        stats.time += now.elapsed().as_millis();
+        stats.depth += total_depth;
+    }
+    graph
+}
+
+// root parallelism: every worker grows a tree of its own from the position, with a generator
+// seeded from this thread's (so that a seed and a thread count give the same searches) and
+// counters of its own, added up at the end like the statistics of their root moves, which go
+// to the first worker's tree, the one kept
+fn grow_parallel(
+    mut graph: Vec<Node>,
+    root: usize,
+    limits: &Limits,
+    rules: Rules,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Vec<Node> {
+    let now = Instant::now();
+    let seeds: Vec<u64> = (0..limits.threads).map(|_| random::bits()).collect();
+    // the helpers share the table's size
+    let helper_mb = (cache.memory() >> 20) / limits.threads;
+    let worker_limits = |first: bool| Limits {
+        threads: 1,
+        stats: Some(Arc::default()),
+        report: limits.report.clone().filter(|_| first),
+        progress: limits.progress.clone().filter(|_| first),
+        ..limits.clone()
+    };
+    let helpers: Vec<(Vec<Node>, Limits, u64)> = seeds[1..]
+        .iter()
+        .map(|seed| (snapshot(&graph, root), worker_limits(false), *seed))
+        .collect();
+    let first = worker_limits(true);
+    // where the helpers' trees start, which is already in the tree
+    let before = root_stats(&graph, root);
+    let mut workers = vec![];
+    thread::scope(|scope| {
+        let handles: Vec<_> = helpers
+            .into_iter()
+            .map(|(tree, limits, seed)| {
+                scope.spawn(move || {
+                    random::seed(seed);
+                    let mut cache = SolverCache::new(helper_mb.max(1));
+                    let tree = grow(tree, 0, &limits, rules, params, &mut cache);
+                    (tree, limits)
+                })
+            })
+            .collect();
+        random::seed(seeds[0]);
+        graph = grow(mem::take(&mut graph), root, &first, rules, params, cache);
+        for handle in handles {
+            workers.push(handle.join().expect("A search thread panicked"));
+        }
+    });
+    for (tree, _) in &workers {
+        merge_root(&mut graph, root, tree, &before);
+    }
+    update_status(root, &mut graph);
+    if let Some(stats) = &limits.stats {
+        let mut total = stats.lock().unwrap();
+        total.searches += 1;
+        total.time += now.elapsed().as_millis();
+        for limits in iter::once(&first).chain(workers.iter().map(|(_, limits)| limits)) {
+            let worker = limits.stats.as_ref().unwrap().lock().unwrap();
+            total.iterations += worker.iterations;
+            total.playouts += worker.playouts;
+            total.nodes += worker.nodes;
+            total.depth += worker.depth;
+        }
+    }
+    graph
+}
+
+// the visits and score of the root, and of each of its moves by position
+type RootStats = ((u32, f32), Vec<((u64, u64), u32, f32)>);
+
+fn root_stats(graph: &[Node], root: usize) -> RootStats {
+    let children = graph[root]
+        .children()
+        .map(|x| (graph[x].state, graph[x].nb_visit, graph[x].score))
+        .collect();
+    ((graph[root].nb_visit, graph[root].score), children)
+}
+
+// adds what a helper found of the root moves (its root being 0) to the tree's, its tree having
+// started from the `before` statistics
+fn merge_root(graph: &mut [Node], root: usize, helper: &[Node], before: &RootStats) {
+    let ((visits, score), children) = before;
+    graph[root].nb_visit += helper[0].nb_visit - visits;
+    graph[root].score += helper[0].score - score;
+    for child in helper[0].children() {
+        let found = graph[root]
+            .children()
+            .find(|x| graph[*x].state == helper[child].state);
+        // a move one of them didn't open, with progressive widening
+        let Some(x) = found else {
+            continue;
+        };
+        let (visits, score) = children
+            .iter()
+            .find(|(state, _, _)| *state == helper[child].state)
+            .map_or((0, 0.0), |(_, visits, score)| (*visits, *score));
+        graph[x].nb_visit += helper[child].nb_visit - visits;
+        graph[x].score += helper[child].score - score;
+        if graph[x].status == STATUS::PLAYING {
+            graph[x].status = helper[child].status.clone();
+        }
+    }
+}
+
+fn mcst(
+    mut graph: Vec<Node>,
+    root: usize,
+    limits: &Limits,
+    rules: Rules,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> (f64, (u64, u64), Vec<Node>, usize) {
+    let (p1, p2) = graph[root].state;
+    if graph[root].nb_children == 0 && !is_over(p1, p2) {
+        // solved on expansion, its own children have to be there to pick a move
+        graph[root].status = STATUS::PLAYING;
+    }
+    graph = if limits.threads > 1 {
+        grow_parallel(graph, root, limits, rules, params, cache)
+    } else {
+        grow(graph, root, limits, rules, params, cache)
+    };
+    let allowed = |x: &usize| !limits.excluded.contains(&graph[*x].state);
+    // proven losing moves are only played when nothing else is left
+    let mut candidates: Vec<usize> = viable_children(&graph, root)
+        .into_iter()
+        .filter(allowed)
+        .collect();
+    // every move loses, whatever the playouts said before that was proven
+    let lost = candidates.is_empty();
+    if lost {
+        candidates = graph[root].children().filter(allowed).collect();
+        if candidates.is_empty() {
+            // nothing else was legal
+            candidates = graph[root].children().collect();
+        }
+    }
+    if let Some(x) = candidates
+        .iter()
+        .find(|x| graph[**x].status == STATUS::LOST)
+    {
+        return (2.0, graph[*x].state, graph, *x);
+    }
+    // the moves the search never got to have nothing to compare
+    let visited: Vec<usize> = candidates
+        .iter()
+        .filter(|x| graph[**x].nb_visit > 0)
+        .copied()
+        .collect();
+    if !visited.is_empty() {
+        candidates = visited;
+    }
+    let mut best = None;
+    for x in &candidates {
+        if improves(&graph, root, *x, graph[*x].mean(), best, params) {
+            best = Some((graph[*x].mean(), *x));
+        }
+    }
+    let (best_score, best_x) = best.unwrap();
+    if params.temperature > 0.0 {
+        let x = pick_with_temperature(&graph, &candidates, best_score, params.temperature);
+        return (graph[x].mean(), graph[x].state, graph, x);
+    }
+    let score = if lost { 0.0 } else { best_score };
+    (score, graph[best_x].state, graph, best_x)
+}
+
+// the plies to the end of the game along the proof of the node's status, for the player to move,
+// None while it is unproven
+fn proof_distance(
+    graph: &[Node],
+    node: usize,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Option<u32> {
+    let status = &graph[node].status;
+    let (p1, p2) = graph[node].state;
+    let empty = 42 - (p1 | p2).count_ones();
+    match status {
+        STATUS::PLAYING => return None,
+        // only a full board is a draw
+        STATUS::DRAW => return Some(empty),
+        _ if is_over(p1, p2) => return Some(0),
+        _ => {}
+    }
+    if graph[node].nb_children == 0 {
+        // proven on expansion, by the solver or by the threats alone
+        if empty <= params.solver_empty && rules::is_standard() {
+            // the score is the number of empty cells left when it ends, plus one
+            return Some(empty + 1 - solver::solve(p1, p2, cache).unsigned_abs());
+        }
+        return Some(if *status == STATUS::WON { 1 } else { 2 });
+    }
+    let mut distances = vec![];
+    for child in graph[node].children() {
+        // the winner takes the shortest win, the loser holds out the longest
+        if *status == STATUS::LOST || graph[child].status == STATUS::LOST {
+            distances.extend(proof_distance(graph, child, params, cache));
+        }
+    }
+    let distance = match status {
+        STATUS::WON => distances.into_iter().min(),
+        _ => distances.into_iter().max(),
+    };
+    distance.map(|distance| distance + 1)
+}
+
+// `win in 7 plies`, for the player to move
+fn proof_text(status: &STATUS, plies: u32) -> String {
+    match status {
+        STATUS::WON => format!("win in {plies} plies"),
+        STATUS::LOST => format!("loss in {plies} plies"),
+        _ => "draw".to_string(),
+    }
+}
+
+// softmax over the children's mean scores
+fn pick_with_temperature(
+    graph: &[Node],
+    candidates: &[usize],
+    best_score: f64,
+    temperature: f64,
+) -> usize {
+    let weights: Vec<f64> = candidates
+        .iter()
+        .map(|x| ((graph[*x].mean() - best_score) / temperature).exp())
+        .collect();
+    let mut pick = random::uniform(weights.iter().sum::<f64>());
+    for (x, weight) in candidates.iter().zip(&weights) {
+        if pick < *weight {
+            return *x;
+        }
+        pick -= weight;
+    }
+    *candidates.last().unwrap()
+}
+
+// `first` when the first player is to move
+fn init_graph(state: (u64, u64), first: bool) -> Vec<Node> {
+    let status = get_status(state.0, state.1, first);
+    vec![Node::new(state, None, status, false, first)]
+}
+
+// a new tree whose root only opens the given moves, the search can't pick any other
+fn restricted_graph(
+    state: (u64, u64),
+    first: bool,
+    can_swap: bool,
+    moves: Vec<(u64, u64)>,
+    rules: Rules,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Vec<Node> {
+    let mut graph = init_graph(state, first);
+    graph[0].can_swap = can_swap;
+    expansion(0, &mut graph, moves, rules, params, cache);
+    graph
+}
+
+// moves the root onto the child matching the new state, a node of its own when there is none
+// (the old root is left to the garbage collection)
+fn advance_root(graph: &mut Vec<Node>, root: usize, state: (u64, u64), rules: Rules) -> usize {
+    let matching: Vec<usize> = graph[root]
+        .children()
+        .filter(|child| graph[*child].state == state)
+        .collect();
+    match matching[..] {
+        [child] => return child,
+        [] if graph[root].nb_children == 0 => {}
+        [] => eprintln!("warning: the move to {state:x?} has no node in the tree, starting over"),
+        _ => {
+            // can't happen from get_node_moves, whatever the rules
+            eprintln!("warning: several nodes for {state:x?}, keeping the most visited");
+            return *matching
+                .iter()
+                .max_by_key(|child| graph[**child].nb_visit)
+                .unwrap();
+        }
+    }
+    // not searched yet
+    let can_swap = rules.swap && graph[root].state == (0, 0);
+    let first = !graph[root].first;
+    let status = get_status(state.0, state.1, first);
+    graph.push(Node::new(state, None, status, can_swap, first));
+    graph.len() - 1
+}
+
+// drops the nodes the root can't reach, the others are renumbered breadth first from the root (0).
+// The nodes move within the arena, whose capacity stays what the largest tree needed: the next
+// expansions take the freed slots at its end instead of growing it, and long games or analyses
+// don't allocate a new tree every move
+fn collect_garbage(mut graph: Vec<Node>, root: usize) -> (Vec<Node>, usize) {
+    let (order, mut new_index) = breadth_first(&graph, root);
+    for old in &order {
+        let node = &mut graph[*old];
+        // whatever was above the root is gone
+        node.parent = node.parent().and_then(|parent| new_index[parent]);
+        if node.nb_children > 0 {
+            node.first_child = new_index[node.first_child as usize].unwrap();
+        }
+    }
+    // each swap puts a node in its new place for good
+    for i in 0..graph.len() {
+        while let Some(new) = new_index[i].map(|new| new as usize).filter(|new| *new != i) {
+            graph.swap(i, new);
+            new_index.swap(i, new);
+        }
+    }
+    graph.truncate(order.len());
+    (graph, 0)
+}
+
+// the nodes below the root, breadth first (which keeps the children next to each other), and
+// the index each node of the tree gets in that order
+fn breadth_first(graph: &[Node], root: usize) -> (Vec<usize>, Vec<Option<u32>>) {
+    let mut new_index: Vec<Option<u32>> = vec![None; graph.len()];
+    new_index[root] = Some(0);
+    let mut order = vec![root];
+    let mut i = 0;
+    while i < order.len() {
+        for child in graph[order[i]].children() {
+            new_index[child] = Some(order.len() as u32);
+            order.push(child);
+        }
+        i += 1;
+    }
+    (order, new_index)
+}
+
+// a tree of its own for the nodes below `node`, which is its root (0): a what-if search grows
+// it without the statistics reaching the game's tree, and only what's below the line explored
+// gets copied
+fn snapshot(graph: &[Node], node: usize) -> Vec<Node> {
+    let (order, new_index) = breadth_first(graph, node);
+    order
+        .iter()
+        .map(|old| {
+            let mut node = graph[*old].clone();
+            node.parent = node.parent().and_then(|parent| new_index[parent]);
+            if node.nb_children > 0 {
+                node.first_child = new_index[node.first_child as usize].unwrap();
+            }
+            node
+        })
+        .collect()
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{message}");
+    eprintln!("{USAGE}");
+    process::exit(1);
+}
+
+fn parse_value<T: FromStr>(name: &str, value: Option<String>) -> T {
+    match value.and_then(|value| value.parse().ok()) {
+        Some(value) => value,
+        None => usage_error(&format!("{name} expects a valid value")),
+    }
+}
+
+fn find_named<T: Copy>(registry: &[(&str, T)], kind: &str, name: &str) -> T {
+    registry
+        .iter()
+        .find(|(entry, _)| *entry == name)
+        .map(|(_, value)| *value)
+        .unwrap_or_else(|| usage_error(&format!("unknown {kind}: {name}")))
+}
+
+fn parse_options(args: Vec<String>) -> Options {
+    let mut options = Options {
+        rules: Rules { swap: false },
+        profile: "default".to_string(),
+        params: get_profile("default").unwrap(),
+        random_moves: 0,
+        extra_disc: None,
+        time_handicap: 0,
+        explain: false,
+        coach: false,
+        player: None,
+        players_file: players::DEFAULT_FILE.to_string(),
+        adaptive: false,
+        model_opponent: false,
+        tt_mb: tt::DEFAULT_MB,
+        max_memory_mb: DEFAULT_MAX_MEMORY,
+        record: None,
+        annotate: false,
+        telemetry: None,
+        opponent: None,
+        human_time: None,
+        time_curve: timing::Curve::default(),
+        flag_fall: FlagFall::Forfeit,
+        forget: false,
+        theme: theme::Theme::plain(),
+        my_symbol: None,
+        zero_based: false,
+        describe: false,
+        live: false,
+        seed: None,
+        iterations: None,
+        book: None,
+        variant: rules::STANDARD,
+        strict: false,
+        fast: false,
+        threads: 1,
+    };
+    // applied over whichever profile is picked
+    let mut backpropagation = None;
+    let mut playout = None;
+    let mut widening = None;
+    let mut tie_break = None;
+    let mut max_playout = None;
+    let mut cutoff = None;
+    let mut tablebase_playouts = false;
+    let mut bias = None;
+    let mut symbols = None;
+    let mut deterministic = false;
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--swap" => options.rules.swap = true,
+            "--explain" => options.explain = true,
+            "--coach" => options.coach = true,
+            "--random-moves" => options.random_moves = parse_value(&arg, args.next()),
+            "--extra-disc" => {
+                let column: u32 = parse_value(&arg, args.next());
+                if !(1..=7).contains(&column) {
+                    usage_error("--extra-disc expects a column between 1 and 7");
+                }
+                options.extra_disc = Some(column);
+            }
+            "--time-handicap" => options.time_handicap = parse_value(&arg, args.next()),
+            "--profile" => {
+                let name: String = parse_value(&arg, args.next());
+                options.params = get_profile(&name)
+                    .unwrap_or_else(|| usage_error(&format!("unknown profile: {name}")));
+                options.profile = name;
+            }
+            "--player" => options.player = Some(parse_value(&arg, args.next())),
+            "--players-file" => options.players_file = parse_value(&arg, args.next()),
+            "--adaptive" => options.adaptive = true,
+            "--model-opponent" => options.model_opponent = true,
+            "--tt-mb" => options.tt_mb = parse_value(&arg, args.next()),
+            "--backprop" => {
+                let name: String = parse_value(&arg, args.next());
+                backpropagation = Some(find_named(&BACKPROPAGATIONS, "backpropagation", &name));
+            }
+            "--strict" => options.strict = true,
+            "--scoring" => {
+                let name: String = parse_value(&arg, args.next());
+                options.variant.scoring = find_named(&rules::SCORINGS, "scoring", &name);
+            }
+            "--full-board" => {
+                let name: String = parse_value(&arg, args.next());
+                options.variant.full_board = find_named(&rules::FULL_BOARDS, "full board", &name);
+            }
+            "--playout" => {
+                let name: String = parse_value(&arg, args.next());
+                playout = Some(find_named(&PLAYOUTS, "playout", &name));
+            }
+            "--bias" => bias = Some(parse_value(&arg, args.next())),
+            "--widening" => widening = Some(parse_value(&arg, args.next())),
+            "--tie-break" => {
+                let name: String = parse_value(&arg, args.next());
+                tie_break = Some(find_named(&TIE_BREAKS, "tie break", &name));
+            }
+            "--tablebase-playouts" => tablebase_playouts = true,
+            "--max-playout" => max_playout = Some(parse_value(&arg, args.next())),
+            "--cutoff" => {
+                let name: String = parse_value(&arg, args.next());
+                cutoff = Some(find_named(&CUTOFFS, "cutoff", &name));
+            }
+            "--max-memory" => options.max_memory_mb = parse_value(&arg, args.next()),
+            "--record" => options.record = Some(parse_value(&arg, args.next())),
+            "--annotate" => options.annotate = true,
+            "--telemetry" => options.telemetry = Some(parse_value(&arg, args.next())),
+            #[cfg(feature = "hooks")]
+            "--hook" => {
+                let program: String = parse_value(&arg, args.next());
+                hooks::register(
+                    hooks::program(&program).unwrap_or_else(|error| usage_error(&error)),
+                );
+            }
+            "--opponent" => options.opponent = Some(parse_value(&arg, args.next())),
+            "--forget" => options.forget = true,
+            "--symbols" => {
+                let text: String = parse_value(&arg, args.next());
+                symbols = Some(
+                    Symbols::parse(&text)
+                        .unwrap_or_else(|| usage_error("--symbols takes two different symbols")),
+                );
+            }
+            "--theme" => {
+                let name: String = parse_value(&arg, args.next());
+                options.theme = theme::load(&name).unwrap_or_else(|error| usage_error(&error));
+            }
+            "--zero-based" => options.zero_based = true,
+            "--describe" => options.describe = true,
+            "--live" => options.live = true,
+            "--threads" => options.threads = parse_value(&arg, args.next()),
+            "--me" => {
+                let text: String = parse_value(&arg, args.next());
+                let mut chars = text.chars();
+                options.my_symbol = match (chars.next(), chars.next()) {
+                    (Some(c), None) if !"#_ ".contains(c) => Some(c),
+                    _ => usage_error("--me takes one symbol"),
+                };
+            }
+            "--seed" => options.seed = Some(parse_value(&arg, args.next())),
+            "--iterations" => options.iterations = Some(parse_value(&arg, args.next())),
+            "--deterministic" => deterministic = true,
+            "--book" => options.book = Some(parse_value(&arg, args.next())),
+            "--human-time" => options.human_time = Some(parse_value(&arg, args.next())),
+            "--time-curve" => {
+                let path: String = parse_value(&arg, args.next());
+                options.time_curve =
+                    timing::load(&path).unwrap_or_else(|error| usage_error(&error));
+            }
+            "--on-flag" => {
+                let name: String = parse_value(&arg, args.next());
+                options.flag_fall = find_named(&FLAG_FALLS, "flag fall", &name);
+            }
+            _ => usage_error(&format!("unknown argument: {arg}")),
+        }
+    }
+    if let Some(backpropagation) = backpropagation {
+        options.params.backpropagation = backpropagation;
+    }
+    if let Some(playout) = playout {
+        options.params.playout = playout;
+    }
+    if let Some(widening) = widening {
+        options.params.widening = widening;
+    }
+    if let Some(tie_break) = tie_break {
+        options.params.tie_break = tie_break;
+    }
+    if let Some(max_playout) = max_playout {
+        options.params.max_playout = max_playout;
+    }
+    if let Some(cutoff) = cutoff {
+        options.params.cutoff = cutoff;
+    }
+    options.params.tablebase_playouts |= tablebase_playouts;
+    if let Some(bias) = bias {
+        options.params.bias = bias;
+    }
+    if let Some(symbols) = symbols {
+        options.theme.symbols = symbols;
+    }
+    if deterministic {
+        options.seed = options.seed.or(Some(DETERMINISTIC_SEED));
+        options.iterations = options.iterations.or(Some(DETERMINISTIC_ITERATIONS));
+    }
+    if options.iterations.is_some() {
+        options.params.max_iterations = options.iterations;
+    }
+    if options.adaptive && options.player.is_none() {
+        usage_error("--adaptive needs a --player to follow");
+    }
+    if options.profile == FAST_PROFILE {
+        options.fast = true;
+        options.tt_mb = options.tt_mb.min(FAST_TT_MB);
+        options.max_memory_mb = options.max_memory_mb.min(FAST_MAX_MEMORY);
+        options.model_opponent = false;
+        options.threads = 1;
+    }
+    if options.threads == 0 {
+        usage_error("--threads needs one thread or more");
+    }
+    if options.model_opponent && options.player.is_none() {
+        usage_error("--model-opponent needs a --player to follow");
+    }
+    // the built-in players only know games from the empty board, and have no levels
+    if options.opponent.is_some() && (options.extra_disc.is_some() || options.adaptive) {
+        usage_error("--opponent can't be combined with --extra-disc or --adaptive");
+    }
+    // the book's positions are the games' from the empty board
+    if options.book.is_some() && options.extra_disc.is_some() {
+        usage_error("--book can't be combined with --extra-disc");
+    }
+    // both have their own ways of counting the searches
+    if options.iterations.is_some() && (options.opponent.is_some() || options.adaptive) {
+        usage_error("--iterations can't be combined with --opponent or --adaptive");
+    }
+    if options.book.is_some() && options.variant != rules::STANDARD {
+        usage_error("--book only learns from games under the standard rules");
+    }
+    // every search of the process plays by them
+    rules::set(options.variant);
+    board::set_strict(options.strict);
+    options
+}
+
+// the command line (see USAGE), which src/main.rs hands over to
+pub fn run() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("puzzles") => return puzzles::main(&args[1..]),
+        Some("inspect-tree") => return dump::inspect(&args[1..]),
+        Some("fuzz") => return fuzz::main(&args[1..]),
+        Some("compare-policies") => return compare::main(&args[1..]),
+        Some("bench-storage") => return storage::main(&args[1..]),
+        Some("uci-dataset") => return dataset::main(&args[1..]),
+        Some("protocol") => return protocol::main(parse_options(args[1..].to_vec())),
+        Some("simple") => return controller::main(parse_options(args[1..].to_vec())),
+        Some("connect") if args.len() > 1 => {
+            return protocol::connect(&args[1], parse_options(args[2..].to_vec()))
+        }
+        Some("match-host") => return matches::host(&args[1..]),
+        Some("self-play") => return matches::self_play(&args[1..]),
+        Some("round-robin") => return matches::round_robin(&args[1..]),
+        Some("show-record") => return record::main(&args[1..]),
+        Some("analyze") => return analyze::main(&args[1..]),
+        Some("analyze-file") => return positions::main(&args[1..]),
+        Some("annotate") => return annotate::main(&args[1..]),
+        Some("audit") => return audit::main(&args[1..]),
+        Some("estimate-elo") => return elo::main(&args[1..]),
+        Some("--version") => {
+            let params = parse_options(args[1..].to_vec()).params;
+            return println!("{}", protocol::identity(&params).join("\n"));
+        }
+        #[cfg(feature = "grpc")]
+        Some("grpc-server") => return grpc::main(&args[1..]),
+        _ => {}
+    }
+    let daily = args.first().map(String::as_str) == Some("daily");
+    let options = parse_options(args[daily as usize..].to_vec());
+    let mut known_players = match &options.player {
+        Some(name) => {
+            let mut known_players =
+                players::load(&options.players_file).unwrap_or_else(|error| usage_error(&error));
+            players::find_or_create(&mut known_players, name).print_summary();
+            known_players
+        }
+        None => vec![],
+    };
+    let mut session = Session::new(options.tt_mb);
+    if daily {
+        if options.extra_disc.is_some() {
+            usage_error("the daily challenge has its own position, without --extra-disc");
+        }
+        let (date, moves) = daily::challenge();
+        println!("daily challenge of {date}: your move, and win it");
+        let challenge = Challenge {
+            name: "daily".to_string(),
+            event: format!("daily challenge {date}"),
+            moves,
+        };
+        // the human is to move
+        let player_turn = (challenge.moves.len() % 2) as i32;
+        play_game(
+            &options,
+            &mut known_players,
+            player_turn,
+            &mut session,
+            Some(&challenge),
+        );
+        return;
+    }
+    let mut player_turn = get_player_turn();
+    loop {
+        let outcome = play_game(
+            &options,
+            &mut known_players,
+            player_turn,
+            &mut session,
+            None,
+        );
+        session.results[outcome as usize] += 1;
+        let [wins, losses, draws] = session.results;
+        println!("session: you {wins} - {losses} me, {draws} draws");
+        println!("play again y/n");
+        if !read_yes() {
+            break;
+        }
+        if options.forget {
+            session = Session::new(options.tt_mb);
+        }
+        player_turn = get_player_turn();
+    }
+}
+
+// a game starting from a given position rather than the empty board
+struct Challenge {
+    // the difficulty it counts as in the players' statistics
+    name: String,
+    event: String,
+    moves: Vec<String>,
+}
+
+// one game against the human, player_turn being 0 when they have the first seat
+fn play_game(
+    options: &Options,
+    known_players: &mut Vec<players::Player>,
+    player_turn: i32,
+    session: &mut Session,
+    challenge: Option<&Challenge>,
+) -> players::Outcome {
+    let rules = options.rules;
+    let mut params = options.params;
+    let mut p1 = 0;
+    let mut p2 = 0;
+    let mut theme = options.theme.clone();
+    let symbols = &mut theme.symbols;
+    if let Some(mine) = options.my_symbol {
+        let (seat, other) = if player_turn == 0 {
+            (&mut symbols.first, &mut symbols.second)
+        } else {
+            (&mut symbols.second, &mut symbols.first)
+        };
+        if *other == mine {
+            *other = *seat;
+        }
+        *seat = mine;
+    }
+    let Symbols { first, second } = *symbols;
+    if challenge.is_some() {
+        let (mine, theirs) = symbols.of(player_turn == 0);
+        println!("you play {mine}, I play {theirs}");
+    } else if player_turn == 0 {
+        println!("you play {first} and move first, I play {second}");
+    } else {
+        println!("I play {first} and move first, you play {second}");
+    }
+    if let Some(column) = options.extra_disc {
+        // the human's disc, whoever moves first
+        let disc = 1 << (column - 1);
+        if player_turn == 0 {
+            p1 = disc;
+        } else {
+            p2 = disc;
+        }
+        if options.describe {
+            let names = [&describe::YOU, &describe::ME];
+            let names = if player_turn == 0 {
+                names
+            } else {
+                [names[1], names[0]]
+            };
+            println!("you start with a disc in column {column}");
+            for line in describe::describe_board(p1, p2, names) {
+                println!("{line}");
+            }
+        } else {
+            show_grid(p1, p2, &theme, true);
+        }
+    }
+    let mut bot_time = TIME_PER_MOVE.saturating_sub(options.time_handicap);
+    let mut difficulty = format!("{} {bot_time}ms", options.profile);
+    if options.adaptive {
+        let name = options.player.as_ref().unwrap();
+        let level = players::find_or_create(known_players, name).level;
+        println!("adaptive difficulty: level {level} out of {MAX_LEVEL}");
+        params = adapt_to_level(options.params, level);
+        difficulty += &format!(" level {level}");
+    }
+    for (name, value) in &session.settings {
+        // they all went through set_option already
+        set_option(&mut params, &mut bot_time, name, value).unwrap();
+    }
+    if let Some(seed) = options.seed {
+        random::seed(seed);
+    }
+    // the searches run to params.max_iterations, however long it takes
+    let clock = |time| options.iterations.map_or(time, |_| u128::MAX);
+    let human = options.player.clone().unwrap_or("human".to_string());
+    let mut opponent = options
+        .opponent
+        .as_ref()
+        .map(|name| opponents::by_name(name).unwrap_or_else(|error| usage_error(&error)));
+    let bot = match &opponent {
+        Some(opponent) => opponent.name(),
+        None => format!("{} {difficulty}", protocol::ENGINE_NAME),
+    };
+    let mut record = if player_turn == 0 {
+        Record::new(&human, &bot, rules)
+    } else {
+        Record::new(&bot, &human, rules)
+    };
+    if let Some(column) = options.extra_disc {
+        let side = if player_turn == 0 { "first" } else { "second" };
+        record.set_tag("Setup", &format!("{side} {column}"));
+    }
+    if theme.symbols != SYMBOLS {
+        record.set_tag("Symbols", &theme.symbols.to_string());
+    }
+    // what the previous games found from the same start
+    let tree = session
+        .tree
+        .take()
+        .filter(|(graph, root)| graph[*root].state == (p1, p2));
+    if let Some((graph, root)) = &tree {
+        println!(
+            "carrying over the last game's tree, {} visits",
+            graph[*root].nb_visit
+        );
+    }
+    let cache = mem::replace(&mut session.cache, SolverCache::new(0));
+    let mut game = GameController::new(rules, params, clock(bot_time), 0)
+        .with_record(record)
+        .with_cache(cache)
+        .with_time_curve(options.time_curve.clone())
+        .with_threads(options.threads)
+        .starting_from((p1, p2), tree);
+    if options.fast {
+        game = game.capped(options.max_memory_mb);
+    }
+    // the bot's, after every ply
+    let mut win_probability: Vec<f64> = vec![];
+    let mut bot_moves = 0;
+    if let Some(challenge) = challenge {
+        game.set_tag("Event", &challenge.event);
+        game.set_tag("Opening", &challenge.moves.join(" "));
+        for text in &challenge.moves {
+            let state = parse_move(game.state(), false, text).expect("Invalid challenge");
+            game.play(state, None).unwrap();
+            // nothing was searched for these
+            win_probability.push(0.5);
+        }
+        let (p1, p2) = game.state();
+        if options.describe {
+            for line in describe::describe_board(p1, p2, [&describe::YOU, &describe::ME]) {
+                println!("{line}");
+            }
+        } else {
+            show_grid(p1, p2, &theme, game.to_move() == 0);
+        }
+    }
+    // the start position, until its tree is kept for the next game
+    let start = game.state();
+    let mut start_root = (!options.forget).then_some(game.root());
+    let mut lost_on_time = false;
+    let opening_book = options.book.as_ref().map(|path| {
+        book::load(path).unwrap_or_else(|error| {
+            eprintln!("{error}");
+            book::Book::default()
+        })
+    });
+    // for the book, from the bot's searches
+    let mut learnt = vec![];
+    // the human's past choices, whose moves from the empty board they are
+    let model = match &options.player {
+        Some(name) if options.model_opponent && options.extra_disc.is_none() => {
+            Some(&*players::find_or_create(known_players, name))
+        }
+        _ => None,
+    };
+    // the human's moves the model guessed, out of those it had a guess for
+    let mut guesses = [0, 0];
+    while game.result() == GameResult::Playing {
+        let (p1, p2) = game.state();
+        // the ply about to be played
+        let turn = game.moves().len() as i32;
+        if turn % 2 == player_turn {
+            // player turn
+            if options.coach {
+                // a quick look from the human's side, so blunders can be caught
+                let params = Params {
+                    contempt: 0.0,
+                    temperature: 0.0,
+                    ..params
+                };
+                let (graph, root, cache) = game.parts();
+                (_, _, *graph, _) = mcst(
+                    mem::take(graph),
+                    root,
+                    &Limits::movetime(clock(COACH_TIME)),
+                    rules,
+                    &params,
+                    cache,
+                );
+            }
+            // the clock starts once the coach is done
+            let deadline = options
+                .human_time
+                .map(|time| Instant::now() + Duration::from_millis(time as u64));
+            let previous_state = (p1, p2);
+            let mut guess = None;
+            loop {
+                let commands = ["dump", "more", "whatif", "board", "set"];
+                let can_swap = game.can_swap();
+                let pondering = model.and_then(|model| {
+                    let mut line: Vec<String> =
+                        game.moves().iter().map(|text| text.to_string()).collect();
+                    line.push(model.predict(&line)?.to_string());
+                    let book = opening_book.as_ref();
+                    let (graph, root, cache) = game.parts();
+                    start_pondering(graph, root, &line, book, rules, &params, cache)
+                });
+                let input =
+                    get_user_input(p1, p2, can_swap, &commands, deadline, options.zero_based);
+                if let Some((search, state)) = pondering {
+                    let (graph, _, cache) = game.parts();
+                    stop_pondering(search, graph, cache);
+                    guess = guess.or(Some(state));
+                }
+                let state = match input {
+                    UserInput::Move(state) => state,
+                    UserInput::TimedOut if options.flag_fall == FlagFall::Forfeit => {
+                        println!("time's up");
+                        game.forfeit("time forfeit").unwrap();
+                        lost_on_time = true;
+                        break;
+                    }
+                    UserInput::TimedOut => {
+                        let moves = get_node_moves(&game.graph()[game.root()]);
+                        let state = moves[random::below(moves.len())];
+                        let name = move_name((p1, p2), state);
+                        println!("time's up, {name} is played at random");
+                        game.play(state, Some("time's up, random".to_string()))
+                            .unwrap();
+                        break;
+                    }
+                    UserInput::Command(words) if words[0] == "more" => {
+                        // more [MS]: searches the position some more, on top of what's known
+                        let Some(time) = words
+                            .get(1)
+                            .map_or(Some(TIME_PER_MOVE), |time| time.parse().ok())
+                        else {
+                            println!("more expects a time in milliseconds");
+                            continue;
+                        };
+                        let params = Params {
+                            contempt: 0.0,
+                            temperature: 0.0,
+                            ..params
+                        };
+                        let (graph, root, cache) = game.parts();
+                        let (score, best);
+                        (score, best, *graph, _) = mcst(
+                            mem::take(graph),
+                            root,
+                            &Limits::movetime(time).with_reports(print_report((p1, p2))),
+                            rules,
+                            &params,
+                            cache,
+                        );
+                        println!(
+                            "{} visits, best move {}, evaluation {}",
+                            graph[root].nb_visit,
+                            move_name((p1, p2), best),
+                            scale::percent(score)
+                        );
+                        continue;
+                    }
+                    UserInput::Command(words) if words[0] == "whatif" => {
+                        // whatif COLUMNS [MS]: searches the position after the moves on a
+                        // snapshot of the tree, the game's own left as it was
+                        let line = words.get(1).and_then(|columns| {
+                            columns
+                                .chars()
+                                .map(|c| parse_column(&c.to_string(), options.zero_based))
+                                .collect::<Option<Vec<u32>>>()
+                        });
+                        let time = words
+                            .get(2)
+                            .map_or(Some(TIME_PER_MOVE), |time| time.parse().ok());
+                        let (Some(line), Some(time)) = (line, time) else {
+                            println!(
+                                "whatif expects columns (as in 34) and a time in milliseconds"
+                            );
+                            continue;
+                        };
+                        let (graph, root, cache) = game.parts();
+                        match what_if(graph, root, &line, time, rules, &params, cache) {
+                            Ok(text) => println!("{text}"),
+                            Err(error) => println!("{error}"),
+                        }
+                        continue;
+                    }
+                    UserInput::Command(words) if words[0] == "set" => {
+                        // set [NAME VALUE]: for the bot's next searches, this game's tree kept
+                        match &words[1..] {
+                            [] => println!("{}", settings(&params, bot_time)),
+                            [name, value] => {
+                                match set_option(&mut params, &mut bot_time, name, value) {
+                                    Ok(()) => {
+                                        game.set_engine(params, clock(bot_time));
+                                        println!("{name} set to {value}");
+                                        session.settings.push((name.clone(), value.clone()));
+                                    }
+                                    Err(error) => println!("{error}"),
+                                }
+                            }
+                            _ => println!("set expects an option and its value"),
+                        }
+                        continue;
+                    }
+                    UserInput::Command(words) => {
+                        run_command(&words, game.graph(), game.root());
+                        continue;
+                    }
+                };
+                if !options.coach || coach_approves(game.graph(), game.root(), state) {
+                    game.play(state, None).unwrap();
+                    break;
+                }
+            }
+            if lost_on_time {
+                break;
+            }
+            if let Some(guess) = guess {
+                guesses[0] += (guess == game.state()) as u32;
+                guesses[1] += 1;
+            }
+            show_position(
+                options,
+                &theme,
+                previous_state,
+                game.state(),
+                turn,
+                player_turn,
+            );
+            let node = &game.graph()[game.root()];
+            win_probability.push(tree_probability(node, false, &win_probability));
+        } else if bot_moves < options.random_moves {
+            // bot turn, handicapped
+            let previous_state = (p1, p2);
+            let previous_root = game.root();
+            let moves = get_moves(p1, p2);
+            game.play(
+                moves[random::below(moves.len())],
+                Some("random".to_string()),
+            )
+            .unwrap();
+            let node = &game.graph()[game.root()];
+            win_probability.push(tree_probability(node, true, &win_probability));
+            show_position(
+                options,
+                &theme,
+                previous_state,
+                game.state(),
+                turn,
+                player_turn,
+            );
+            println!(
+                "I played {} (random)",
+                to_user_move(previous_state, game.state())
+            );
+            if options.explain {
+                explain::explain_move(
+                    game.graph(),
+                    previous_state,
+                    previous_root,
+                    game.root(),
+                    "random (handicap)",
+                );
+            }
+            bot_moves += 1;
+        } else if let Some(opponent) = &mut opponent {
+            // bot turn, a built-in player standing in
+            let previous_state = (p1, p2);
+            let moves: Vec<String> = game.moves().iter().map(|text| text.to_string()).collect();
+            let (text, _) = opponent
+                .best_move(&moves, bot_time)
+                .unwrap_or_else(|error| usage_error(&error));
+            let state = parse_move(previous_state, game.can_swap(), &text).unwrap();
+            let comment = opponent.comment();
+            game.play(state, comment.clone()).unwrap();
+            let node = &game.graph()[game.root()];
+            win_probability.push(tree_probability(node, true, &win_probability));
+            show_position(
+                options,
+                &theme,
+                previous_state,
+                game.state(),
+                turn,
+                player_turn,
+            );
+            println!("I played {}", to_user_move(previous_state, game.state()));
+            if let Some(comment) = comment {
+                println!("{comment}");
+            }
+            bot_moves += 1;
+        } else {
+            // bot turn
+            if let Some(start_index) = start_root.filter(|index| *index != game.root()) {
+                // the collection would drop the start position
+                session.tree = Some((game.graph().to_vec(), start_index));
+                start_root = None;
+            }
+            game.compact();
+            let previous_state = (p1, p2);
+            let previous_root = game.root();
+            let moves: Vec<String> = game.moves().iter().map(|text| text.to_string()).collect();
+            let position = book::key(&moves);
+            let in_book = opening_book.is_some() && moves.len() < book::PLIES;
+            let time = game.move_time();
+            let live = options.live && !options.describe && !options.fast;
+            let (graph, root, cache) = game.parts();
+            if let Some(book) = opening_book.as_ref().filter(|_| in_book) {
+                if book.prime(&position, graph, root, rules, &params, cache) {
+                    println!("the book knows this position");
+                }
+            }
+            let before = book::snapshot(graph, root);
+            // the search keeps it up to date from the root's moves as they stand
+            let progress: search::Progress =
+                Arc::new(Mutex::new(search::root_children(graph, root)));
+            let budget = match options.iterations {
+                Some(iterations) => spinner::Budget::Iterations(iterations, progress.clone()),
+                None => spinner::Budget::Time(time),
+            };
+            let spinner = (!live && !options.describe && !options.fast)
+                .then(|| spinner::Spinner::start(budget));
+            // long searches say how they are doing on their own too, above the bar, or in a chart
+            // following them
+            let reports = |limits: Limits| match options.fast || live {
+                true => limits,
+                false => limits
+                    .with_reports(print_report((p1, p2)))
+                    .with_progress(progress.clone()),
+            };
+            let watch = |search| live::watch(search, (p1, p2), time);
+            let reply = game
+                .play_engine_with(reports, live.then_some(&watch as &dyn Fn(_) -> _))
+                .unwrap();
+            if let Some(spinner) = spinner {
+                spinner.stop();
+            }
+            let (score, best) = (reply.eval, game.state());
+            if in_book {
+                let graph = game.graph();
+                learnt.extend(book::learnt(&position, graph, previous_root, &before));
+            }
+            if start_root.take().is_some() && previous_state == start {
+                // the bot moved first, the start is the position it searched
+                session.tree = Some((game.graph().to_vec(), previous_root));
+            }
+            win_probability.push(scale::probability(score));
+            show_position(options, &theme, previous_state, best, turn, player_turn);
+            if previous_state == best {
+                println!("I swap sides");
+            } else {
+                println!("I played {}", to_user_move(previous_state, best));
+            }
+            if options.describe {
+                println!("evaluation: {}", scale::percent(score));
+            } else {
+                println!("evaluation: {}", scale::bar(score));
+            }
+            let (graph, root, cache) = game.parts();
+            if let Some(plies) = proof_distance(graph, previous_root, &params, cache) {
+                let status = &graph[previous_root].status;
+                println!(
+                    "proven: {}, the search stopped there",
+                    proof_text(status, plies)
+                );
+            }
+            if !options.fast {
+                if cache.hits + cache.misses > 0 {
+                    println!("transposition table: {}", cache.summary());
+                }
+                report_memory(graph, cache, options.max_memory_mb);
+            }
+            if is_only_move(graph, previous_root) {
+                println!("only move, everything else loses");
+            }
+            if options.explain {
+                explain::explain_move(graph, previous_state, previous_root, root, "search");
+            }
+            bot_moves += 1;
+        }
+    }
+    // 0 for the first player, which turn 0 was
+    let human = player_turn as usize;
+    let (outcome, announcement, bot_chances) = match game.result() {
+        GameResult::Won(seat) if seat == human => (players::Outcome::Win, "you win", 0.0),
+        GameResult::Won(_) => (players::Outcome::Loss, "I win", 1.0),
+        _ => (players::Outcome::Draw, "draw, the board is full", 0.5),
+    };
+    println!("{announcement}");
+    let stats = *game.stats().lock().unwrap();
+    if stats.searches > 0 {
+        println!("my searches: {}", stats.summary());
+    }
+    let (game, graph, cache) = game.finish();
+    session.cache = cache;
+    let cache = &mut session.cache;
+    if guesses[1] > 0 {
+        println!("I saw {} of your {} moves coming", guesses[0], guesses[1]);
+    }
+    // the last one is known for sure
+    if let Some(last) = win_probability.last_mut() {
+        *last = bot_chances;
+    }
+    println!("my winning chances, move after move:");
+    println!("{}", telemetry::graph(&win_probability));
+    if let Some(path) = &options.telemetry {
+        if let Err(error) = telemetry::append(path, &game, &win_probability) {
+            eprintln!("{error}");
+        }
+    }
+    if let Some(path) = &options.record {
+        match record::append(path, &game) {
+            Ok(()) => println!("game saved to {path}"),
+            Err(error) => eprintln!("{error}"),
+        }
+    }
+    if let Some(path) = &options.book {
+        match book::update(path, &learnt) {
+            Ok(size) => println!("book updated, {size} moves in {path}"),
+            Err(error) => eprintln!("{error}"),
+        }
+    }
+    if options.annotate {
+        let params = Params {
+            contempt: 0.0,
+            temperature: 0.0,
+            ..params
+        };
+        match annotate::annotate(&game, annotate::ANALYSIS_TIME, &params, cache) {
+            Ok((annotated, counts)) => {
+                print!("{}", annotated.format());
+                annotate::print_summary(&annotated, &counts);
+            }
+            Err(error) => eprintln!("{error}"),
+        }
+    }
+    if let Some(start_index) = start_root {
+        session.tree = Some((graph, start_index));
+    }
+    if let Some(name) = &options.player {
+        let player = players::find_or_create(known_players, name);
+        player.add_result(
+            challenge.map_or(&difficulty, |challenge| &challenge.name),
+            outcome,
+        );
+        // the challenge's opening wasn't theirs, and another start has other positions
+        if options.extra_disc.is_none() {
+            let from = challenge.map_or(0, |challenge| challenge.moves.len());
+            player.add_choices(&game.moves, player_turn as usize, from);
+        }
+        player.print_summary();
+        if options.adaptive {
+            // one level up after a win and down after a loss settles around even results
+            player.level = match outcome {
+                players::Outcome::Win => (player.level + 1).min(MAX_LEVEL),
+                players::Outcome::Loss => player.level.saturating_sub(1).max(1),
+                players::Outcome::Draw => player.level,
+            };
+            println!("next adaptive level: {}", player.level);
+        }
+        if let Err(error) = players::save(&options.players_file, known_players) {
+            eprintln!("{error}");
+        }
+    }
+    outcome
+}
+
+// --model-opponent: while the human thinks, searches the position after the reply their past
+// games make the likeliest (the last move of `line`, the ones from the empty board), from the
+// book's statistics when it has some. The search has the tree and the cache until
+// stop_pondering, and the reply is handed back with it
+fn start_pondering(
+    graph: &mut Vec<Node>,
+    root: usize,
+    line: &[String],
+    opening_book: Option<&book::Book>,
+    rules: Rules,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Option<(search::SearchFuture, (u64, u64))> {
+    let text = line.last()?;
+    let state = parse_move(graph[root].state, graph[root].can_swap, text).ok()?;
+    if graph[root].nb_children == 0 {
+        let moves = get_node_moves(&graph[root]);
+        expansion(root, graph, moves, rules, params, cache);
+    }
+    let child = graph[root]
+        .children()
+        .find(|child| graph[*child].state == state)?;
+    if graph[child].status != STATUS::PLAYING {
+        return None;
+    }
+    if let Some(book) = opening_book.filter(|_| line.len() < book::PLIES) {
+        book.prime(&book::key(line), graph, child, rules, params, cache);
+    }
+    let position = search::Position {
+        graph: mem::take(graph),
+        root: child,
+        rules,
+        params: *params,
+        cache: mem::replace(cache, SolverCache::new(0)),
+    };
+    Some((search::search(position, Limits::movetime(u128::MAX)), state))
+}
+
+fn stop_pondering(search: search::SearchFuture, graph: &mut Vec<Node>, cache: &mut SolverCache) {
+    search.cancel();
+    let result = search.wait();
+    *graph = result.position.graph;
+    *cache = result.position.cache;
+}
+
+// the position after the move at `turn`, drawn or told in sentences
+fn show_position(
+    options: &Options,
+    theme: &theme::Theme,
+    previous_state: (u64, u64),
+    (p1, p2): (u64, u64),
+    turn: i32,
+    player_turn: i32,
+) {
+    if !options.describe {
+        // the next ply is the first player's after an odd one
+        show_grid(p1, p2, theme, turn % 2 == 1);
+        return;
+    }
+    let human_to_move = (turn + 1) % 2 == player_turn;
+    let names = if human_to_move {
+        [&describe::YOU, &describe::ME]
+    } else {
+        [&describe::ME, &describe::YOU]
+    };
+    let column = (previous_state != (p1, p2)).then(|| to_user_move(previous_state, (p1, p2)));
+    for line in describe::describe_move(p1, p2, column, names) {
+        println!("{line}");
+    }
+}
+
+fn to_user_move(previous_state: (u64, u64), new_state: (u64, u64)) -> u32 {
+    let m = previous_state.0 ^ new_state.1;
+    for y in 0..6 {
+        for x in 0..7 {
+            let i = y * 8 + x;
+            if 1 << i == m {
+                return x + 1;
+            }
+        }
+    }
+    unreachable!();
+}
+
+// the move the way it is typed: its column, or "s" for the swap
+fn move_name(previous_state: (u64, u64), new_state: (u64, u64)) -> String {
+    if previous_state == new_state {
+        return "s".to_string();
+    }
+    to_user_move(previous_state, new_state).to_string()
+}
+
+fn print_report(state: (u64, u64)) -> Reporter {
+    Arc::new(move |report: &Report| {
+        spinner::println(&format!(
+            "{} iterations, {} visits, {} ms: best move {}, evaluation {}",
+            report.iterations,
+            report.visits,
+            report.time,
+            move_name(state, report.best),
+            scale::percent(report.score)
+        ))
+    })
+}
+
+// the bot's winning chances from what the tree knows of the node just moved into, the
+// previous estimate when it knows nothing
+fn tree_probability(node: &Node, bot_moved: bool, previous: &[f64]) -> f64 {
+    if node.nb_visit == 0 && node.status == STATUS::PLAYING {
+        return previous.last().copied().unwrap_or(0.5);
+    }
+    // for the player who moved
+    let chances = scale::probability(node.mean());
+    if bot_moved {
+        chances
+    } else {
+        1.0 - chances
+    }
+}
+
+fn read_yes() -> bool {
+    match input::read_line(None) {
+        input::Line::Text(line) => line == "y" || line == "Y",
+        _ => false,
+    }
+}
+
+fn get_player_turn() -> i32 {
+    println!("do you want to start y/n");
+    if read_yes() {
+        return 0;
+    }
+    1
+}
+
+// warns about moves the coach's search proved bad, returns whether the move stands
+fn coach_approves(graph: &[Node], root: usize, state: (u64, u64)) -> bool {
+    let child = graph[root].children().find(|x| graph[*x].state == state);
+    let warning = match child {
+        Some(x) if graph[x].status == STATUS::WON => "that loses by force",
+        Some(x) if graph[root].status == STATUS::WON && graph[x].status != STATUS::LOST => {
+            "that drops a win"
+        }
+        _ => return true,
+    };
+    println!("{warning}, play anyway? (y/n)");
+    read_yes()
+}
+
+enum UserInput {
+    Move((u64, u64)),
+    // one of the accepted commands, split into words
+    Command(Vec<String>),
+    TimedOut,
+}
+
+// the search of the position after the columns, from what the tree knows of it
+fn what_if(
+    graph: &[Node],
+    root: usize,
+    line: &[u32],
+    time: u128,
+    rules: Rules,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Result<String, String> {
+    let mut state = graph[root].state;
+    let mut node = Some(root);
+    for column in line {
+        if is_over(state.0, state.1) {
+            return Err("the game is over before the end of the line".to_string());
+        }
+        state = play_column(state.0, state.1, *column)
+            .ok_or_else(|| MoveError::ColumnFull(*column).to_string())?;
+        node = node.and_then(|node| graph[node].children().find(|x| graph[*x].state == state));
+    }
+    if is_over(state.0, state.1) {
+        return Err("the game is over after the line".to_string());
+    }
+    let first = graph[root].first != (line.len() % 2 == 1);
+    let copy = node.map_or_else(|| init_graph(state, first), |node| snapshot(graph, node));
+    let known = copy[0].nb_visit;
+    let params = Params {
+        contempt: 0.0,
+        temperature: 0.0,
+        ..*params
+    };
+    let (score, best, copy, chosen) = mcst(copy, 0, &Limits::movetime(time), rules, &params, cache);
+    let mut pv = vec![move_name(state, best)];
+    pv.extend(principal_variation(&copy, chosen));
+    Ok(format!(
+        "best reply {}, evaluation {} for the side to move, {} visits ({known} from the game's tree), main line {}",
+        pv[0],
+        scale::percent(score),
+        copy[0].nb_visit,
+        pv.join(" ")
+    ))
+}
+
+fn run_command(words: &[String], graph: &[Node], root: usize) {
+    match words[0].as_str() {
+        "board" => {
+            // the human is to move
+            let (p1, p2) = graph[root].state;
+            for line in describe::describe_board(p1, p2, [&describe::YOU, &describe::ME]) {
+                println!("{line}");
+            }
+        }
+        "dump" => {
+            let path = words.get(1).map_or("tree.txt", String::as_str);
+            match dump::save(path, graph, root) {
+                Ok(()) => println!("tree saved to {path}"),
+                Err(error) => println!("{error}"),
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn get_user_move(p1: u64, p2: u64, can_swap: bool) -> (u64, u64) {
+    match get_user_input(p1, p2, can_swap, &[], None, false) {
+        UserInput::Move(state) => state,
+        _ => unreachable!(),
+    }
+}
+
+fn get_user_input(
+    p1: u64,
+    p2: u64,
+    can_swap: bool,
+    commands: &[&str],
+    deadline: Option<Instant>,
+    zero_based: bool,
+) -> UserInput {
+    // why the last input wasn't a move
+    let mut error = None;
+    // as the human numbers them
+    let shown = |column: u32| (column - zero_based as u32).to_string();
+    loop {
+        if let Some(deadline) = deadline {
+            let left = deadline.saturating_duration_since(Instant::now());
+            println!("{:.1}s left", left.as_secs_f64());
+        }
+        match error {
+            None => {
+                if zero_based {
+                    println!("entrez un coup (le x 0 -> 6 inclus, ou a -> g)");
+                } else {
+                    println!("entrez un coup (le x 1 -> 7 inclus, ou a -> g)");
+                }
+                if can_swap {
+                    println!("ou 's' pour échanger les camps");
+                }
+            }
+            Some(MoveError::ColumnFull(column)) => {
+                let legal: Vec<String> = board::legal_columns(p1, p2)
+                    .into_iter()
+                    .map(shown)
+                    .collect();
+                println!(
+                    "la colonne {} est pleine, colonnes possibles : {}",
+                    shown(column),
+                    legal.join(" ")
+                );
+            }
+            Some(_) => println!("coup entré invalide"),
+        }
+        let input = match input::read_line(deadline) {
+            input::Line::Text(line) => line,
+            input::Line::TimedOut => return UserInput::TimedOut,
+            // nobody is left to play
+            input::Line::Closed => process::exit(0),
+        };
+        let words: Vec<String> = input.split_whitespace().map(String::from).collect();
+        if words
+            .first()
+            .is_some_and(|word| commands.contains(&word.as_str()))
+        {
+            return UserInput::Command(words);
+        }
+        match parse_typed_move((p1, p2), can_swap, &input, zero_based) {
+            Ok(state) => return UserInput::Move(state),
+            Err(reason) => error = Some(reason),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the move a --deterministic search of the empty board picks, the nodes it grew and the
+    // root's visits
+    fn deterministic_search(threads: usize) -> ((u64, u64), usize, u32) {
+        let args = ["--deterministic", "--threads", &threads.to_string()].map(String::from);
+        let options = parse_options(args.to_vec());
+        random::seed(options.seed.unwrap());
+        let limits = Limits::movetime(u128::MAX).with_threads(options.threads);
+        let (_, best, graph, _) = mcst(
+            init_graph((0, 0), true),
+            0,
+            &limits,
+            options.rules,
+            &options.params,
+            &mut SolverCache::new(1),
+        );
+        (best, graph.len(), graph[0].nb_visit)
+    }
+
+    #[test]
+    fn deterministic_searches_repeat() {
+        for threads in [1, 3] {
+            let first = deterministic_search(threads);
+            assert_eq!(deterministic_search(threads), first, "{threads} threads");
+        }
+    }
+
+    fn searched(iterations: u64) -> Vec<Node> {
+        let params = Params {
+            max_iterations: Some(iterations),
+            ..get_profile("default").unwrap()
+        };
+        let limits = Limits::movetime(u128::MAX);
+        let rules = Rules { swap: false };
+        grow(
+            init_graph((0, 0), true),
+            0,
+            &limits,
+            rules,
+            &params,
+            &mut SolverCache::new(1),
+        )
+    }
+
+    #[test]
+    fn merging_an_idle_helper_changes_nothing() {
+        let graph = searched(500);
+        let before = root_stats(&graph, 0);
+        let mut merged = graph.clone();
+        merge_root(&mut merged, 0, &snapshot(&graph, 0), &before);
+        assert_eq!(root_stats(&merged, 0), before);
+    }
+
+    #[test]
+    fn merging_counts_only_what_the_helper_added() {
+        let graph = searched(500);
+        let before = root_stats(&graph, 0);
+        let params = Params {
+            max_iterations: Some(300),
+            ..get_profile("default").unwrap()
+        };
+        let limits = Limits::movetime(u128::MAX);
+        let rules = Rules { swap: false };
+        let mut cache = SolverCache::new(1);
+        let helper = grow(snapshot(&graph, 0), 0, &limits, rules, &params, &mut cache);
+        let mut merged = graph.clone();
+        merge_root(&mut merged, 0, &helper, &before);
+        let added = helper[0].nb_visit - graph[0].nb_visit;
+        assert!(added > 0);
+        assert_eq!(merged[0].nb_visit, graph[0].nb_visit + added);
+        let children_visits =
+            |graph: &[Node]| -> u32 { graph[0].children().map(|x| graph[x].nb_visit).sum() };
+        assert_eq!(
+            children_visits(&merged) - children_visits(&graph),
+            children_visits(&helper) - children_visits(&graph)
+        );
+    }
+}
@@ -0,0 +1,72 @@
+// --live: while the bot thinks, a chart of its moves takes the place of the spinner, redrawn
+// in place ten times a second from the background search's progress:
+//
+//     thinking 1.4s / 3.0s
+//     4  [########################] 412345 visits  55.2%
+//     3  [#########...............] 160211 visits  51.8%
+//     5  [####....................]  70112 visits  49.1%
+//
+// the bars being the visits against the most visited move's and the percentages the bot's
+// chances after each move (see scale.rs). The chart goes away once the move is found, and
+// nothing is drawn when the output isn't a terminal.
+use std::{
+    io::{self, IsTerminal, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    move_name, scale,
+    search::{RootChild, SearchFuture, SearchResult},
+};
+
+const WIDTH: usize = 24;
+const REDRAW: Duration = Duration::from_millis(100);
+
+fn chart(state: (u64, u64), children: &[RootChild], elapsed: Duration, time: Duration) -> String {
+    let mut lines = vec![format!(
+        "thinking {:.1}s / {:.1}s",
+        elapsed.as_secs_f64().min(time.as_secs_f64()),
+        time.as_secs_f64()
+    )];
+    let most = children.iter().map(|child| child.visits).max().unwrap_or(0);
+    for child in children {
+        let filled = (WIDTH as f64 * child.visits as f64 / most.max(1) as f64).round() as usize;
+        lines.push(format!(
+            "{:<3}[{}{}] {:>7} visits  {}",
+            move_name(state, child.state),
+            "#".repeat(filled),
+            ".".repeat(WIDTH - filled),
+            child.visits,
+            scale::percent(child.mean)
+        ));
+    }
+    lines.join("\n")
+}
+
+// the search's result, once it's over, `time` milliseconds being what it was given
+pub fn watch(search: SearchFuture, state: (u64, u64), time: u128) -> SearchResult {
+    if !io::stdout().is_terminal() {
+        return search.wait();
+    }
+    let start = Instant::now();
+    let time = Duration::from_millis(time.min(u64::MAX as u128) as u64);
+    let mut drawn = 0;
+    while !search.is_done() {
+        let text = chart(state, &search.progress(), start.elapsed(), time);
+        // back over the last chart, whose lines are wiped as they are redrawn
+        if drawn > 0 {
+            print!("\r\x1b[{drawn}A");
+        }
+        print!("{}\x1b[J", text.replace('\n', "\x1b[K\n"));
+        io::stdout().flush().ok();
+        drawn = text.matches('\n').count();
+        thread::sleep(REDRAW);
+    }
+    if drawn > 0 {
+        print!("\r\x1b[{drawn}A");
+    }
+    print!("\r\x1b[J");
+    io::stdout().flush().ok();
+    search.wait()
+}
@@ -0,0 +1,605 @@
+// Match manager: plays games between two players and keeps the score, or between every two of
+// several in a round-robin, which ends with the crosstable of their points and ratings fitted
+// to all the results (their average being 0):
+//
+//         player                                    1     2     3  points     elo
+//     1   mcst_connect4 default                     -   3.5   2.0     5.5     +12 +/- 140
+//     2   mcst_connect4 default exploration=0.9   0.5     -   1.5     2.0    -160 +/- 150
+//     3   mcst_connect4 solid                     2.0   2.5     -     4.5     +35 +/- 140
+//
+// Games may start from openings instead of the empty board, each of them played twice so
+// both players get both sides. Opening files have one per line, the columns played from the
+// empty board (`4453`), empty lines and lines starting with # being ignored.
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "hooks")]
+use crate::hooks;
+use crate::{
+    board::{first_to_move, get_moves, is_over, parse_move},
+    book::{self, Book, Stat},
+    elo, get_profile, init_graph, mcst, move_name, opponents, parse_value, random,
+    record::{self, Record},
+    solver::SolverCache,
+    suite, usage_error, GameController, GameResult, Limits, Params, Rules,
+};
+
+// what a reply may take on top of the move time, for the network
+const TIME_MARGIN: u128 = 200; // milliseconds
+
+// of the round-robin's rating fit, each player's rating taking the others' as they were
+const RATING_ROUNDS: usize = 50;
+
+// ends the games whose outcome both engines already agree on, to get through long runs
+#[derive(Clone, Copy)]
+pub struct Adjudication {
+    // evaluations in a row that have to agree, from both sides
+    pub moves: usize,
+    // how close to 0 or 2 an evaluation has to be to call the game
+    pub win_margin: f64,
+    // how close to 1 they have to stay for a draw, from ply draw_from on
+    pub draw_margin: f64,
+    pub draw_from: usize,
+}
+
+pub const ADJUDICATION: Adjudication = Adjudication {
+    moves: 4,
+    win_margin: 0.05,
+    draw_margin: 0.05,
+    draw_from: 30,
+};
+
+impl Adjudication {
+    // Some(winner) once the game can be called, None for a draw, the evaluations being
+    // for the first player
+    fn verdict(&self, evals: &[f64]) -> Option<Option<usize>> {
+        if evals.len() < self.moves.max(2) {
+            return None;
+        }
+        let last = &evals[evals.len() - self.moves.max(2)..];
+        if last.iter().all(|eval| *eval >= 2.0 - self.win_margin) {
+            Some(Some(0))
+        } else if last.iter().all(|eval| *eval <= self.win_margin) {
+            Some(Some(1))
+        } else if evals.len() >= self.draw_from
+            && last
+                .iter()
+                .all(|eval| (eval - 1.0).abs() <= self.draw_margin)
+        {
+            Some(None)
+        } else {
+            None
+        }
+    }
+}
+
+pub trait Player {
+    fn name(&self) -> String;
+    fn new_game(&mut self, rules: Rules) -> Result<(), String>;
+    // the move to play after `moves`, with its evaluation (0 -> 2) for the player
+    fn best_move(&mut self, moves: &[String], time: u128) -> Result<(String, f64), String>;
+    // the book for the next game's searches, for the players that have some
+    fn open_book(&mut self, _book: &Book) {}
+    // what the searches found in the book's positions since the last call
+    fn learnt(&mut self) -> Vec<Stat> {
+        vec![]
+    }
+    // a word on how the last move was chosen, for the players that have one
+    fn comment(&mut self) -> Option<String> {
+        None
+    }
+}
+
+// an engine connected to the host, speaking the text protocol
+struct Remote {
+    name: String,
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Remote {
+    fn new(stream: TcpStream) -> Result<Remote, String> {
+        let reader = BufReader::new(stream.try_clone().map_err(|error| error.to_string())?);
+        let mut remote = Remote {
+            name: String::new(),
+            reader,
+            writer: stream,
+        };
+        remote.send("hello")?;
+        let reply = remote.receive(None)?;
+        remote.name = reply
+            .strip_prefix("hello ")
+            .ok_or_else(|| format!("unexpected greeting: {reply}"))?
+            .to_string();
+        Ok(remote)
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.writer, "{line}").map_err(|error| format!("connection lost: {error}"))
+    }
+
+    fn receive(&mut self, timeout: Option<Duration>) -> Result<String, String> {
+        self.reader
+            .get_ref()
+            .set_read_timeout(timeout)
+            .map_err(|error| error.to_string())?;
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => Err("connection closed".to_string()),
+            Ok(_) => Ok(line.trim().to_string()),
+            Err(_) if timeout.is_some() => Err("lost on time".to_string()),
+            Err(error) => Err(format!("connection lost: {error}")),
+        }
+    }
+}
+
+impl Player for Remote {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self, rules: Rules) -> Result<(), String> {
+        self.send(if rules.swap {
+            "newgame swap"
+        } else {
+            "newgame"
+        })
+    }
+
+    fn best_move(&mut self, moves: &[String], time: u128) -> Result<(String, f64), String> {
+        self.send(&format!("position {}", moves.join(" ")))?;
+        self.send(&format!("go movetime {time}"))?;
+        let now = Instant::now();
+        let deadline = Duration::from_millis((time + TIME_MARGIN) as u64);
+        // the info lines of long searches only say how the search is going
+        let reply = loop {
+            let left = deadline.saturating_sub(now.elapsed());
+            let reply = self.receive(Some(left.max(Duration::from_millis(1))))?;
+            if now.elapsed() > deadline {
+                return Err("lost on time".to_string());
+            }
+            if !reply.starts_with("info") {
+                break reply;
+            }
+        };
+        match reply.split_whitespace().collect::<Vec<&str>>()[..] {
+            ["bestmove", best, "eval", eval] => Ok((best.to_string(), eval.parse().unwrap_or(1.0))),
+            ["bestmove", best] => Ok((best.to_string(), 1.0)),
+            _ => Err(format!("unexpected reply: {reply}")),
+        }
+    }
+}
+
+pub struct GameRecord {
+    // the moves with what the mover said of each (nothing for the opening's), and the result
+    pub record: Record,
+    // index of the winning player, None for a draw
+    pub winner: Option<usize>,
+    pub reason: String,
+}
+
+// how far from even a random opening may be
+const OPENING_BALANCE: f64 = 0.2;
+// the cheap search judging that
+const OPENING_ITERATIONS: u64 = 20_000;
+const OPENING_TRIES: usize = 100;
+
+pub fn load_openings(path: &str) -> Result<Vec<Vec<String>>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}"))?;
+    let mut openings = vec![];
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let columns = line
+            .chars()
+            .map(|c| c.to_digit(10))
+            .collect::<Option<Vec<u32>>>();
+        match columns.and_then(|columns| Some((suite::replay(&columns)?, columns))) {
+            Some(((p1, p2), columns)) if !is_over(p1, p2) => {
+                openings.push(columns.iter().map(|column| column.to_string()).collect())
+            }
+            _ => return Err(format!("{path}:{}: invalid opening", n + 1)),
+        }
+    }
+    if openings.is_empty() {
+        return Err(format!("{path}: no openings"));
+    }
+    Ok(openings)
+}
+
+// random moves, kept when a short search finds the position close to even
+pub fn random_opening(plies: usize) -> Vec<String> {
+    let params = Params {
+        max_iterations: Some(OPENING_ITERATIONS),
+        ..get_profile("default").unwrap()
+    };
+    let mut cache = SolverCache::new(1);
+    let mut opening = vec![];
+    for _ in 0..OPENING_TRIES {
+        opening.clear();
+        let mut state = (0, 0);
+        while opening.len() < plies && !is_over(state.0, state.1) {
+            let moves = get_moves(state.0, state.1);
+            let next = moves[random::below(moves.len())];
+            opening.push(move_name(state, next));
+            state = next;
+        }
+        if is_over(state.0, state.1) {
+            continue;
+        }
+        let (score, _, _, _) = mcst(
+            init_graph(state, first_to_move(opening.len())),
+            0,
+            &Limits::movetime(u128::MAX),
+            Rules { swap: false },
+            &params,
+            &mut cache,
+        );
+        if (score - 1.0).abs() <= OPENING_BALANCE {
+            break;
+        }
+    }
+    opening
+}
+
+pub struct Settings {
+    pub games: u32,
+    pub rules: Rules,
+    // per move, milliseconds
+    pub time: u128,
+    pub adjudication: Option<Adjudication>,
+    pub openings: Vec<Vec<String>>,
+    // the file every game is appended to
+    pub record: Option<String>,
+    // the opening book the engines start from and learn into, see book.rs
+    pub book: Option<String>,
+}
+
+// players[0] moves first from the opening, the host checks every move and the time
+pub fn play_game(
+    players: &mut [&mut dyn Player; 2],
+    settings: &Settings,
+    opening: &[String],
+) -> GameRecord {
+    let (rules, time) = (settings.rules, settings.time);
+    let names = [players[0].name(), players[1].name()];
+    // the host's side of the game, which doesn't search: the players do
+    let mut game = GameController::new(rules, get_profile("default").unwrap(), time, 0)
+        .with_record(Record::new(&names[0], &names[1], rules));
+    if !opening.is_empty() {
+        game.set_tag("Opening", &opening.join(" "));
+    }
+    for text in opening {
+        let state = parse_move(game.state(), game.can_swap(), text).expect("Invalid opening");
+        game.play(state, None).unwrap();
+    }
+    // for players[0], whoever gave them
+    let mut first_evals = vec![];
+    let decided = |mut game: GameController, winner: Option<usize>, reason: String| {
+        let result = winner.map_or(GameResult::Drawn, GameResult::Won);
+        game.adjudicate(result, &reason).unwrap();
+        let (record, _, _) = game.finish();
+        GameRecord {
+            record,
+            winner,
+            reason,
+        }
+    };
+    for (i, player) in players.iter_mut().enumerate() {
+        if let Err(error) = player.new_game(rules) {
+            return decided(game, Some(1 - i), error);
+        }
+    }
+    while game.result() == GameResult::Playing {
+        let mover = game.to_move();
+        let moves: Vec<String> = game.moves().iter().map(|text| text.to_string()).collect();
+        let (text, eval) = match players[mover].best_move(&moves, time) {
+            Ok(reply) => reply,
+            Err(error) => return decided(game, Some(1 - mover), error),
+        };
+        let state = match parse_move(game.state(), game.can_swap(), &text) {
+            Ok(state) => state,
+            Err(error) => {
+                let reason = format!("illegal move {text}: {error}");
+                return decided(game, Some(1 - mover), reason);
+            }
+        };
+        game.play(state, Some(format!("eval {eval:.4}"))).unwrap();
+        first_evals.push(if mover == 0 { eval } else { 2.0 - eval });
+        if game.result() != GameResult::Playing {
+            break;
+        }
+        let verdict = settings
+            .adjudication
+            .and_then(|rule| rule.verdict(&first_evals));
+        if let Some(winner) = verdict {
+            return decided(game, winner, "adjudicated".to_string());
+        }
+    }
+    let (winner, reason) = match game.result() {
+        GameResult::Won(winner) => (Some(winner), "four in a row"),
+        _ => (None, "full board"),
+    };
+    game.set_tag("Termination", reason);
+    let (record, _, _) = game.finish();
+    GameRecord {
+        record,
+        winner,
+        reason: reason.to_string(),
+    }
+}
+
+// plays the games, alternating who starts, and returns both players' points, the openings
+// going by pairs of games
+pub fn run_match(players: &mut [&mut dyn Player; 2], settings: &Settings) -> [f64; 2] {
+    let mut points = [0.0; 2];
+    let openings = &settings.openings;
+    for game in 0..settings.games {
+        let opening = match openings.len() {
+            0 => &[][..],
+            len => &openings[(game as usize / 2) % len][..],
+        };
+        // the order of this game, players[order[0]] starting
+        let order = if game % 2 == 0 { [0, 1] } else { [1, 0] };
+        let [a, b] = players;
+        let mut seated: [&mut dyn Player; 2] = if order[0] == 0 { [*a, *b] } else { [*b, *a] };
+        if let Some(path) = &settings.book {
+            let opening_book = book::load(path).unwrap_or_else(|error| usage_error(&error));
+            for player in seated.iter_mut() {
+                player.open_book(&opening_book);
+            }
+        }
+        let record = play_game(&mut seated, settings, opening);
+        if let Some(path) = &settings.book {
+            let learnt: Vec<Stat> = seated
+                .iter_mut()
+                .flat_map(|player| player.learnt())
+                .collect();
+            if let Err(error) = book::update(path, &learnt) {
+                eprintln!("{error}");
+            }
+        }
+        let result = match record.winner {
+            Some(winner) => {
+                points[order[winner]] += 1.0;
+                format!("{} wins", seated[winner].name())
+            }
+            None => {
+                points[0] += 0.5;
+                points[1] += 0.5;
+                "draw".to_string()
+            }
+        };
+        let moves: Vec<&str> = record
+            .record
+            .moves
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect();
+        println!(
+            "game {}: {} - {}: {result} ({}), moves {}",
+            game + 1,
+            seated[0].name(),
+            seated[1].name(),
+            record.reason,
+            moves.join(" ")
+        );
+        if let Some(path) = &settings.record {
+            if let Err(error) = record::append(path, &record.record) {
+                eprintln!("{error}");
+            }
+        }
+    }
+    points
+}
+
+// the flags of match-host and self-play: `[--games N] [--time MS] [--swap] [--adjudicate]
+//     [--openings FILE | --random-openings PLIES] [--record FILE] [--book FILE]
+//     [--hook PROGRAM]`, the last with the hooks feature
+fn parse_settings(args: &[String]) -> Settings {
+    let mut settings = Settings {
+        games: 2,
+        rules: Rules { swap: false },
+        time: 1000,
+        adjudication: None,
+        openings: vec![],
+        record: None,
+        book: None,
+    };
+    let mut random_plies = None;
+    let mut rest = args.iter().cloned();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--games" => settings.games = parse_value(&arg, rest.next()),
+            "--time" => settings.time = parse_value(&arg, rest.next()),
+            "--swap" => settings.rules.swap = true,
+            "--adjudicate" => settings.adjudication = Some(ADJUDICATION),
+            "--openings" => {
+                let path = rest
+                    .next()
+                    .unwrap_or_else(|| usage_error("--openings needs a file"));
+                settings.openings =
+                    load_openings(&path).unwrap_or_else(|error| usage_error(&error));
+            }
+            "--random-openings" => random_plies = Some(parse_value(&arg, rest.next())),
+            "--record" => settings.record = Some(parse_value(&arg, rest.next())),
+            "--book" => settings.book = Some(parse_value(&arg, rest.next())),
+            #[cfg(feature = "hooks")]
+            "--hook" => {
+                let program: String = parse_value(&arg, rest.next());
+                hooks::register(
+                    hooks::program(&program).unwrap_or_else(|error| usage_error(&error)),
+                );
+            }
+            _ => usage_error(&format!("unknown argument: {arg}")),
+        }
+    }
+    if let Some(plies) = random_plies {
+        settings.openings = (0..settings.games.div_ceil(2))
+            .map(|_| random_opening(plies))
+            .collect();
+    }
+    settings
+}
+
+fn print_score(names: [String; 2], points: [f64; 2]) {
+    println!(
+        "final score: {} {} - {} {}",
+        names[0], points[0], points[1], names[1]
+    );
+}
+
+// `self-play A B [SETTINGS]`: a match between two built-in players, see opponents::by_name
+pub fn self_play(args: &[String]) {
+    if args.len() < 2 {
+        usage_error("self-play needs two players");
+    }
+    let mut players: Vec<Box<dyn Player>> = args[..2]
+        .iter()
+        .map(|name| opponents::by_name(name).unwrap_or_else(|error| usage_error(&error)))
+        .collect();
+    let settings = parse_settings(&args[2..]);
+    let names = [players[0].name(), players[1].name()];
+    let [a, b] = &mut players[..] else {
+        unreachable!()
+    };
+    let points = run_match(&mut [a.as_mut(), b.as_mut()], &settings);
+    print_score(names, points);
+}
+
+// the ratings fitting the points of every pairing, points[i][j] being i's against j over
+// `games` games, each with its standard error
+fn fit_ratings(points: &[Vec<f64>], games: u32) -> Vec<(f64, f64)> {
+    let mut ratings = vec![(0.0, 0.0); points.len()];
+    for _ in 0..RATING_ROUNDS {
+        let next: Vec<(f64, f64)> = (0..points.len())
+            .map(|i| {
+                let results: Vec<(f64, u32, f64)> = (0..points.len())
+                    .filter(|j| *j != i)
+                    .map(|j| (ratings[j].0, games, points[i][j]))
+                    .collect();
+                elo::estimate(&results)
+            })
+            .collect();
+        let average = next.iter().map(|(rating, _)| rating).sum::<f64>() / next.len() as f64;
+        ratings = next
+            .into_iter()
+            .map(|(rating, error)| (rating - average, error))
+            .collect();
+    }
+    ratings
+}
+
+fn print_crosstable(names: &[String], points: &[Vec<f64>], games: u32) {
+    let width = names.iter().map(|name| name.chars().count()).max().unwrap();
+    let ratings = fit_ratings(points, games);
+    let numbers: String = (1..=names.len()).map(|n| format!("{n:>6}")).collect();
+    println!("    {:<width$}{numbers}  points     elo", "player");
+    for (i, name) in names.iter().enumerate() {
+        let row: String = (0..names.len())
+            .map(|j| {
+                if j == i {
+                    format!("{:>6}", "-")
+                } else {
+                    format!("{:>6.1}", points[i][j])
+                }
+            })
+            .collect();
+        let (rating, error) = ratings[i];
+        println!(
+            "{:<4}{name:<width$}{row}{:>8.1}{:>8} +/- {:.0}",
+            i + 1,
+            points[i].iter().sum::<f64>(),
+            format!("{rating:+.0}"),
+            1.96 * error
+        );
+    }
+}
+
+// `round-robin PLAYER PLAYER... [SETTINGS]`: a match between every two of the built-in players
+pub fn round_robin(args: &[String]) {
+    let count = args.iter().take_while(|arg| !arg.starts_with("--")).count();
+    if count < 2 {
+        usage_error("round-robin needs two players or more");
+    }
+    let mut players: Vec<Box<dyn Player>> = args[..count]
+        .iter()
+        .map(|name| opponents::by_name(name).unwrap_or_else(|error| usage_error(&error)))
+        .collect();
+    let settings = parse_settings(&args[count..]);
+    let names: Vec<String> = players.iter().map(|player| player.name()).collect();
+    let mut points = vec![vec![0.0; count]; count];
+    for i in 0..count {
+        for j in i + 1..count {
+            println!("{} against {}", names[i], names[j]);
+            let (left, right) = players.split_at_mut(j);
+            let match_points = run_match(&mut [left[i].as_mut(), right[0].as_mut()], &settings);
+            points[i][j] = match_points[0];
+            points[j][i] = match_points[1];
+        }
+    }
+    print_crosstable(&names, &points, settings.games);
+}
+
+// `match-host ADDR [--local PLAYER] [SETTINGS]`: waits for two engines, or for one when the
+// other is a built-in player
+pub fn host(args: &[String]) {
+    let addr = args
+        .first()
+        .unwrap_or_else(|| usage_error("match-host needs an address to listen on"));
+    let mut rest = args[1..].to_vec();
+    let mut local = None;
+    if let Some(i) = rest.iter().position(|arg| arg == "--local") {
+        let name = rest
+            .get(i + 1)
+            .unwrap_or_else(|| usage_error("--local needs a player"));
+        local = Some(opponents::by_name(name).unwrap_or_else(|error| usage_error(&error)));
+        rest.drain(i..i + 2);
+    }
+    let settings = parse_settings(&rest);
+    let listener = TcpListener::bind(addr)
+        .unwrap_or_else(|error| usage_error(&format!("can't listen on {addr}: {error}")));
+    let wanted = if local.is_some() { 1 } else { 2 };
+    println!(
+        "waiting for {} on {addr}",
+        if wanted == 1 {
+            "an engine"
+        } else {
+            "two engines"
+        }
+    );
+    let mut remotes = vec![];
+    while remotes.len() < wanted {
+        let (stream, peer) = listener.accept().expect("Failed to accept a connection");
+        match Remote::new(stream) {
+            Ok(mut remote) => {
+                // both could be the same engine
+                remote.name = format!("{} #{}", remote.name, remotes.len() + 1);
+                println!("{} connected from {peer}", remote.name);
+                remotes.push(remote);
+            }
+            Err(error) => println!("rejected {peer}: {error}"),
+        }
+    }
+    let mut a = remotes.remove(0);
+    if let Some(mut local) = local {
+        let names = [a.name(), local.name()];
+        let points = run_match(&mut [&mut a, local.as_mut()], &settings);
+        print_score(names, points);
+        a.send("quit").ok();
+        return;
+    }
+    let mut b = remotes.remove(0);
+    let points = run_match(&mut [&mut a, &mut b], &settings);
+    print_score([a.name(), b.name()], points);
+    for remote in [&mut a, &mut b] {
+        remote.send("quit").ok();
+    }
+}
@@ -0,0 +1,400 @@
+// Built-in players of known strength, from a random mover up to short searches: estimate-elo
+// rates the engine against them, and they can stand in for the engine in self-play, in
+// matches and against a human who wants an easier game. None of them takes the swap.
+//
+// By name: random, greedy, minimax:DEPTH, mcts:ITERATIONS, one of the engine's profiles
+// searching for the time it's given, or vote:PROFILE+PROFILE for two of them consulting. A
+// profile may be followed by settings of the protocol's setoption but the time, to try
+// candidates against each other: `default,exploration=0.9,playout=greedy`.
+use crate::{
+    board::{first_to_move, get_moves, get_status, parse_move, Board, Move, STATUS},
+    book::{self, Book, Stat},
+    eval, get_profile, init_graph,
+    matches::Player,
+    mcst, move_name, random, set_option,
+    solver::SolverCache,
+    tt, Limits, Params, Rules,
+};
+
+// the position after `moves`, the host having checked them already
+fn replay(moves: &[String], rules: Rules) -> Result<(u64, u64), String> {
+    let mut state = (0, 0);
+    for (i, text) in moves.iter().enumerate() {
+        state = parse_move(state, rules.swap && i == 1, text)
+            .map_err(|error| format!("illegal move {text}: {error}"))?;
+    }
+    Ok(state)
+}
+
+fn pick(moves: &[(u64, u64)]) -> (u64, u64) {
+    moves[random::below(moves.len())]
+}
+
+pub struct RandomMover {
+    rules: Rules,
+}
+
+impl RandomMover {
+    pub fn new() -> RandomMover {
+        RandomMover {
+            rules: Rules { swap: false },
+        }
+    }
+}
+
+impl Player for RandomMover {
+    fn name(&self) -> String {
+        "random".to_string()
+    }
+
+    fn new_game(&mut self, rules: Rules) -> Result<(), String> {
+        self.rules = rules;
+        Ok(())
+    }
+
+    fn best_move(&mut self, moves: &[String], _time: u128) -> Result<(String, f64), String> {
+        let state = replay(moves, self.rules)?;
+        let next = pick(&get_moves(state.0, state.1));
+        Ok((move_name(state, next), 1.0))
+    }
+}
+
+// wins when it can, blocks when it has to, plays at random otherwise
+pub struct Greedy {
+    rules: Rules,
+}
+
+impl Greedy {
+    pub fn new() -> Greedy {
+        Greedy {
+            rules: Rules { swap: false },
+        }
+    }
+}
+
+impl Player for Greedy {
+    fn name(&self) -> String {
+        "greedy".to_string()
+    }
+
+    fn new_game(&mut self, rules: Rules) -> Result<(), String> {
+        self.rules = rules;
+        Ok(())
+    }
+
+    fn best_move(&mut self, moves: &[String], _time: u128) -> Result<(String, f64), String> {
+        let state = replay(moves, self.rules)?;
+        let options: Vec<Move> = Board::new(state.0, state.1).moves().collect();
+        if let Some(win) = options.iter().find(|option| option.creates_win) {
+            return Ok((move_name(state, win.board.state()), 2.0));
+        }
+        // the opponent's winning cells, taken before they are
+        let blocks: Vec<(u64, u64)> = options
+            .iter()
+            .filter(|option| option.blocks_loss)
+            .map(|option| option.board.state())
+            .collect();
+        let all: Vec<(u64, u64)> = options.iter().map(|option| option.board.state()).collect();
+        let next = pick(if blocks.is_empty() { &all } else { &blocks });
+        Ok((move_name(state, next), 1.0))
+    }
+}
+
+// from -1 to 1 for the player who moved into the position (p2), `first` when p1 is the first
+// player
+fn negamax(p1: u64, p2: u64, first: bool, depth: u32, mut alpha: f64, beta: f64) -> f64 {
+    match get_status(p1, p2, first) {
+        STATUS::LOST => return 1.0,
+        STATUS::DRAW => return 0.0,
+        _ => {}
+    }
+    if depth == 0 {
+        return eval::evaluate(p1, p2);
+    }
+    // the best the player to move can get, from their side
+    let mut best = -1.0;
+    for (q1, q2) in get_moves(p1, p2) {
+        best = f64::max(best, negamax(q1, q2, !first, depth - 1, -beta, -alpha));
+        alpha = alpha.max(best);
+        if alpha >= beta {
+            break;
+        }
+    }
+    -best
+}
+
+// a full-width search of a few plies ending on the static evaluation
+pub struct Minimax {
+    depth: u32,
+    rules: Rules,
+}
+
+impl Minimax {
+    pub fn new(depth: u32) -> Minimax {
+        Minimax {
+            depth: depth.max(1),
+            rules: Rules { swap: false },
+        }
+    }
+}
+
+impl Player for Minimax {
+    fn name(&self) -> String {
+        format!("minimax depth {}", self.depth)
+    }
+
+    fn new_game(&mut self, rules: Rules) -> Result<(), String> {
+        self.rules = rules;
+        Ok(())
+    }
+
+    fn best_move(&mut self, moves: &[String], _time: u128) -> Result<(String, f64), String> {
+        let state = replay(moves, self.rules)?;
+        let options = get_moves(state.0, state.1);
+        let first = first_to_move(moves.len() + 1);
+        let values: Vec<f64> = options
+            .iter()
+            .map(|(q1, q2)| negamax(*q1, *q2, first, self.depth - 1, -1.0, 1.0))
+            .collect();
+        let best = values.iter().copied().fold(f64::MIN, f64::max);
+        // the equal moves in turn, or the same games would be played again and again
+        let equal: Vec<(u64, u64)> = options
+            .into_iter()
+            .zip(values)
+            .filter(|(_, value)| *value == best)
+            .map(|(next, _)| next)
+            .collect();
+        Ok((move_name(state, pick(&equal)), best + 1.0))
+    }
+}
+
+// the engine, searching for the match's time per move or for a fixed number of iterations
+pub struct Engine {
+    name: String,
+    params: Params,
+    // ignores the match's time when set
+    iterations: Option<u64>,
+    rules: Rules,
+    cache: SolverCache,
+    book: Option<Book>,
+    learnt: Vec<Stat>,
+}
+
+impl Engine {
+    pub fn new(profile: &str, params: Params, cache_mb: usize) -> Engine {
+        Engine {
+            name: format!("mcst_connect4 {profile}"),
+            params,
+            iterations: None,
+            rules: Rules { swap: false },
+            cache: SolverCache::new(cache_mb),
+            book: None,
+            learnt: vec![],
+        }
+    }
+
+    pub fn with_iterations(iterations: u64) -> Engine {
+        let params = Params {
+            max_iterations: Some(iterations),
+            // its endgames would be perfect
+            solver_empty: 0,
+            ..get_profile("default").unwrap()
+        };
+        Engine {
+            name: format!("mcts {iterations} iterations"),
+            iterations: Some(iterations),
+            ..Engine::new("default", params, 1)
+        }
+    }
+}
+
+impl Player for Engine {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn new_game(&mut self, rules: Rules) -> Result<(), String> {
+        self.rules = rules;
+        Ok(())
+    }
+
+    fn best_move(&mut self, moves: &[String], time: u128) -> Result<(String, f64), String> {
+        let state = replay(moves, self.rules)?;
+        let mut graph = init_graph(state, first_to_move(moves.len()));
+        graph[0].can_swap = self.rules.swap && moves.len() == 1;
+        let time = if self.iterations.is_some() {
+            u128::MAX
+        } else {
+            time
+        };
+        let position = book::key(moves);
+        let in_book = self.book.is_some() && moves.len() < book::PLIES;
+        if let Some(book) = self.book.as_ref().filter(|_| in_book) {
+            book.prime(
+                &position,
+                &mut graph,
+                0,
+                self.rules,
+                &self.params,
+                &mut self.cache,
+            );
+        }
+        let before = book::snapshot(&graph, 0);
+        let (score, next, graph, _) = mcst(
+            graph,
+            0,
+            &Limits::movetime(time),
+            self.rules,
+            &self.params,
+            &mut self.cache,
+        );
+        if in_book {
+            self.learnt
+                .extend(book::learnt(&position, &graph, 0, &before));
+        }
+        Ok((move_name(state, next), score))
+    }
+
+    fn open_book(&mut self, book: &Book) {
+        self.book = Some(book.clone());
+    }
+
+    fn learnt(&mut self) -> Vec<Stat> {
+        std::mem::take(&mut self.learnt)
+    }
+}
+
+// two engines of different profiles looking at the same position, each for a third of the
+// time: what they agree on is played, otherwise a referee (the default profile) checks both
+// moves with the rest of the time and plays the one leaving the opponent worse off
+pub struct Consultation {
+    profiles: [String; 2],
+    engines: [Engine; 2],
+    referee: Engine,
+    // how the last move was decided
+    comment: Option<String>,
+}
+
+impl Consultation {
+    pub fn new(profiles: [&str; 2]) -> Result<Consultation, String> {
+        let engine = |profile: &str| match get_profile(profile) {
+            Some(params) => Ok(Engine::new(profile, params, tt::DEFAULT_MB)),
+            None => Err(format!("unknown profile: {profile}")),
+        };
+        Ok(Consultation {
+            profiles: profiles.map(String::from),
+            engines: [engine(profiles[0])?, engine(profiles[1])?],
+            referee: engine("default")?,
+            comment: None,
+        })
+    }
+
+    // what the move is worth for the player making it, after the referee's look
+    fn check(&mut self, moves: &[String], text: &str, time: u128) -> Result<f64, String> {
+        let mut after = moves.to_vec();
+        after.push(text.to_string());
+        let (q1, q2) = replay(&after, self.referee.rules)?;
+        Ok(match get_status(q1, q2, first_to_move(after.len())) {
+            STATUS::PLAYING => 2.0 - self.referee.best_move(&after, time)?.1,
+            // the move ended the game
+            STATUS::LOST => 2.0,
+            _ => 1.0,
+        })
+    }
+}
+
+impl Player for Consultation {
+    fn name(&self) -> String {
+        format!("vote {}+{}", self.profiles[0], self.profiles[1])
+    }
+
+    fn new_game(&mut self, rules: Rules) -> Result<(), String> {
+        for engine in &mut self.engines {
+            engine.new_game(rules)?;
+        }
+        self.referee.new_game(rules)
+    }
+
+    fn best_move(&mut self, moves: &[String], time: u128) -> Result<(String, f64), String> {
+        let (a, a_eval) = self.engines[0].best_move(moves, time / 3)?;
+        let (b, b_eval) = self.engines[1].best_move(moves, time / 3)?;
+        let [first, second] = self.profiles.clone();
+        if a == b {
+            self.comment = Some(format!("{first} and {second} agree on {a}"));
+            return Ok((a, (a_eval + b_eval) / 2.0));
+        }
+        let a_value = self.check(moves, &a, time / 6)?;
+        let b_value = self.check(moves, &b, time / 6)?;
+        let picked = if a_value >= b_value {
+            (a.clone(), a_value)
+        } else {
+            (b.clone(), b_value)
+        };
+        self.comment = Some(format!(
+            "{first} wants {a}, {second} wants {b}: the referee picks {}",
+            picked.0
+        ));
+        Ok(picked)
+    }
+
+    fn open_book(&mut self, book: &Book) {
+        for engine in &mut self.engines {
+            engine.open_book(book);
+        }
+    }
+
+    fn learnt(&mut self) -> Vec<Stat> {
+        self.engines
+            .iter_mut()
+            .flat_map(|engine| engine.learnt())
+            .collect()
+    }
+
+    fn comment(&mut self) -> Option<String> {
+        self.comment.take()
+    }
+}
+
+// a profile with its settings, named after them
+fn configured(name: &str) -> Result<Box<dyn Player>, String> {
+    let mut settings = name.split(',');
+    let profile = settings.next().unwrap();
+    let mut params = get_profile(profile).ok_or_else(|| format!("unknown profile: {profile}"))?;
+    for setting in settings {
+        let (key, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("a setting has a value, as in exploration=0.9: {setting}"))?;
+        if key == "time" {
+            return Err("the match sets the time".to_string());
+        }
+        set_option(&mut params, &mut 0, key, value)?;
+    }
+    let label = name.replace(',', " ");
+    Ok(Box::new(Engine::new(&label, params, tt::DEFAULT_MB)))
+}
+
+pub fn by_name(name: &str) -> Result<Box<dyn Player>, String> {
+    if name.contains(',') {
+        return configured(name);
+    }
+    let (kind, value) = name.split_once(':').unwrap_or((name, ""));
+    let number = || {
+        value
+            .parse::<u64>()
+            .map_err(|_| format!("{kind} needs a number, as in {kind}:4"))
+    };
+    Ok(match kind {
+        "random" => Box::new(RandomMover::new()),
+        "greedy" => Box::new(Greedy::new()),
+        "minimax" => Box::new(Minimax::new(number()? as u32)),
+        "mcts" => Box::new(Engine::with_iterations(number()?)),
+        "vote" => match value.split_once('+') {
+            Some((a, b)) => Box::new(Consultation::new([a, b])?),
+            None => return Err("vote needs two profiles, as in vote:aggressive+solid".to_string()),
+        },
+        profile => match get_profile(profile) {
+            Some(params) => Box::new(Engine::new(profile, params, tt::DEFAULT_MB)),
+            None => return Err(format!("unknown player: {name}")),
+        },
+    })
+}
@@ -0,0 +1,246 @@
+// Human player statistics, kept in a tab separated text file:
+//
+//     player  <name>  <streak>  <best streak>
+//     record  <name>  <difficulty>  <wins>  <losses>  <draws>
+//     adaptive  <name>  <level>
+//     choice  <name>  <moves>  <move>  <count>
+//
+// the streak counts the last wins in a row (or losses, as a negative number)
+// and the level is where the adaptive difficulty stands for the player. The choices are how
+// often they played a move in the positions of the first MODELLED_PLIES plies, the moves being
+// the ones from the empty board (`4453`, "-" for none) as in the opening book, which
+// --model-opponent uses to guess their replies.
+use std::{collections::BTreeMap, fs, io::ErrorKind};
+
+use crate::book;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Win,
+    Loss,
+    Draw,
+}
+
+pub struct Record {
+    pub difficulty: String,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+pub struct Player {
+    pub name: String,
+    pub streak: i32,
+    pub best_streak: i32,
+    pub records: Vec<Record>,
+    pub level: u32,
+    // by position then move, how many times they played it
+    pub choices: BTreeMap<(String, String), u32>,
+}
+
+pub const DEFAULT_FILE: &str = "players.txt";
+const DEFAULT_LEVEL: u32 = 5;
+pub const MAX_LEVEL: u32 = 10;
+pub const MODELLED_PLIES: usize = 12;
+
+impl Player {
+    pub fn games(&self) -> u32 {
+        self.records
+            .iter()
+            .map(|record| record.wins + record.losses + record.draws)
+            .sum()
+    }
+
+    pub fn add_result(&mut self, difficulty: &str, outcome: Outcome) {
+        let index = match self
+            .records
+            .iter()
+            .position(|record| record.difficulty == difficulty)
+        {
+            Some(index) => index,
+            None => {
+                self.records.push(Record {
+                    difficulty: difficulty.to_string(),
+                    wins: 0,
+                    losses: 0,
+                    draws: 0,
+                });
+                self.records.len() - 1
+            }
+        };
+        let record = &mut self.records[index];
+        match outcome {
+            Outcome::Win => {
+                record.wins += 1;
+                self.streak = self.streak.max(0) + 1;
+            }
+            Outcome::Loss => {
+                record.losses += 1;
+                self.streak = self.streak.min(0) - 1;
+            }
+            Outcome::Draw => {
+                record.draws += 1;
+                self.streak = 0;
+            }
+        }
+        self.best_streak = self.best_streak.max(self.streak);
+    }
+
+    // the moves of a game they played from `seat` (0 first), those before the ply `from` and
+    // those with a comment (played at random for them) left out
+    pub fn add_choices(&mut self, moves: &[(String, Option<String>)], seat: usize, from: usize) {
+        let texts: Vec<String> = moves.iter().map(|(text, _)| text.clone()).collect();
+        for (ply, (text, comment)) in moves.iter().enumerate().take(MODELLED_PLIES) {
+            if ply < from || ply % 2 != seat || comment.is_some() {
+                continue;
+            }
+            let position = book::key(&texts[..ply]);
+            *self.choices.entry((position, text.clone())).or_insert(0) += 1;
+        }
+    }
+
+    // the move they played the most often in the position, the first of them on a tie
+    pub fn predict(&self, moves: &[String]) -> Option<&str> {
+        let position = book::key(moves);
+        let mut best: Option<(&str, u32)> = None;
+        for ((_, text), count) in self
+            .choices
+            .range((position.clone(), String::new())..)
+            .take_while(|((entry, _), _)| *entry == position)
+        {
+            if best.is_none_or(|(_, most)| *count > most) {
+                best = Some((text, *count));
+            }
+        }
+        best.map(|(text, _)| text)
+    }
+
+    pub fn print_summary(&self) {
+        println!("{}: {} games played", self.name, self.games());
+        for record in &self.records {
+            println!(
+                "  against {}: {} wins, {} losses, {} draws",
+                record.difficulty, record.wins, record.losses, record.draws
+            );
+        }
+        match self.streak {
+            0 => {}
+            streak if streak > 0 => println!("  on a {streak} game winning streak"),
+            streak => println!("  on a {} game losing streak", -streak),
+        }
+        println!("  best winning streak: {}", self.best_streak);
+    }
+}
+
+// a missing file just means no one played yet, one that can't be read stops the save that
+// would overwrite it
+pub fn load(path: &str) -> Result<Vec<Player>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(error) => return Err(format!("can't read {path}: {error}")),
+    };
+    let mut players: Vec<Player> = vec![];
+    for (n, line) in content.lines().enumerate() {
+        let invalid = || format!("{path}:{}: invalid player data", n + 1);
+        let fields: Vec<&str> = line.split('\t').collect();
+        match fields[..] {
+            ["player", name, streak, best_streak] => players.push(Player {
+                name: name.to_string(),
+                streak: streak.parse().map_err(|_| invalid())?,
+                best_streak: best_streak.parse().map_err(|_| invalid())?,
+                records: vec![],
+                level: DEFAULT_LEVEL,
+                choices: BTreeMap::new(),
+            }),
+            ["record", name, difficulty, wins, losses, draws] => {
+                let player = players
+                    .iter_mut()
+                    .find(|player| player.name == name)
+                    .ok_or_else(invalid)?;
+                player.records.push(Record {
+                    difficulty: difficulty.to_string(),
+                    wins: wins.parse().map_err(|_| invalid())?,
+                    losses: losses.parse().map_err(|_| invalid())?,
+                    draws: draws.parse().map_err(|_| invalid())?,
+                });
+            }
+            ["adaptive", name, level] => {
+                let player = players
+                    .iter_mut()
+                    .find(|player| player.name == name)
+                    .ok_or_else(invalid)?;
+                player.level = level
+                    .parse()
+                    .ok()
+                    .filter(|level| (1..=MAX_LEVEL).contains(level))
+                    .ok_or_else(invalid)?;
+            }
+            ["choice", name, position, text, count] => {
+                let player = players
+                    .iter_mut()
+                    .find(|player| player.name == name)
+                    .ok_or_else(invalid)?;
+                let count = count.parse().map_err(|_| invalid())?;
+                player
+                    .choices
+                    .insert((position.to_string(), text.to_string()), count);
+            }
+            [""] => {}
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(players)
+}
+
+pub fn save(path: &str, players: &[Player]) -> Result<(), String> {
+    let mut content = String::new();
+    for player in players {
+        content += &format!(
+            "player\t{}\t{}\t{}\n",
+            player.name, player.streak, player.best_streak
+        );
+        for record in &player.records {
+            content += &format!(
+                "record\t{}\t{}\t{}\t{}\t{}\n",
+                player.name, record.difficulty, record.wins, record.losses, record.draws
+            );
+        }
+        content += &format!("adaptive\t{}\t{}\n", player.name, player.level);
+        for ((position, text), count) in &player.choices {
+            content += &format!("choice\t{}\t{position}\t{text}\t{count}\n", player.name);
+        }
+    }
+    fs::write(path, content).map_err(|error| format!("can't write {path}: {error}"))
+}
+
+pub fn find_or_create<'a>(players: &'a mut Vec<Player>, name: &str) -> &'a mut Player {
+    match players.iter().position(|player| player.name == name) {
+        Some(index) => &mut players[index],
+        None => {
+            players.push(Player {
+                name: name.to_string(),
+                streak: 0,
+                best_streak: 0,
+                records: vec![],
+                level: DEFAULT_LEVEL,
+                choices: BTreeMap::new(),
+            });
+            players.last_mut().unwrap()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_missing_file_is_empty() {
+        let dir = std::env::temp_dir();
+        let missing = dir.join("mcst_connect4_no_such_file");
+        assert!(load(missing.to_str().unwrap()).unwrap().is_empty());
+        // a directory can't be read
+        assert!(load(dir.to_str().unwrap()).is_err());
+    }
+}
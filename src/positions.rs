@@ -0,0 +1,218 @@
+// analyze-file FILE [MS] [--json] [--output FILE]: searches every position of FILE, MS
+// milliseconds each, and writes what was found as csv (or json with --json), to the standard
+// output or FILE, for labelling data sets or building a book. The file has one position a
+// line, either the columns played from the empty board (`4453`, "-" for none) or the grid a row
+// at a time from the top, x being the first player's discs, o the second's and a digit that
+// many empty cells:
+//
+//     7/7/7/7/3o3/2xx3
+//
+// the player to move following from the numbers of discs. Every position gets its best move,
+// its evaluation (from 0 to 2 for the player to move), the line starting with it (the most
+// visited one after the move) and the root's visits:
+//
+//     position,best,eval,pv,visits
+//     4453,3,1.1052,3424,51234
+//
+// and a position that can't be read or is over is reported on the standard error and skipped.
+use std::fs;
+
+use crate::{
+    board::{first_to_move, is_over, Board},
+    explain::principal_variation,
+    get_profile, init_graph, mcst, move_name,
+    solver::SolverCache,
+    suite,
+    telemetry::json_string,
+    tt, usage_error, Limits, Params, Rules, TIME_PER_MOVE,
+};
+
+// what the search found of a position
+struct Analysis {
+    position: String,
+    best: String,
+    eval: f64,
+    pv: Vec<String>,
+    visits: u32,
+}
+
+// the grid's cells, the top row first, and whether the first player is to move
+fn parse_grid(text: &str) -> Result<((u64, u64), bool), String> {
+    let rows: Vec<&str> = text.split('/').collect();
+    if rows.len() != 6 {
+        return Err("a grid has 6 rows".to_string());
+    }
+    let (mut first, mut second) = (0u64, 0u64);
+    for (i, row) in rows.iter().enumerate() {
+        let y = 5 - i as u32;
+        let mut x = 0;
+        for c in row.chars() {
+            match c {
+                '1'..='7' => x += c.to_digit(10).unwrap(),
+                'x' | 'o' if x < 7 => {
+                    let cell = 1 << (y * 8 + x);
+                    if c == 'x' {
+                        first |= cell;
+                    } else {
+                        second |= cell;
+                    }
+                    x += 1;
+                }
+                _ => return Err(format!("invalid row {row}")),
+            }
+        }
+        if x != 7 {
+            return Err(format!("the row {row} doesn't have 7 cells"));
+        }
+    }
+    // the first player is to move when both have as many discs
+    let to_move = first.count_ones() == second.count_ones();
+    let state = if to_move {
+        (first, second)
+    } else {
+        (second, first)
+    };
+    Board::new(state.0, state.1)
+        .validate()
+        .map_err(|error| format!("impossible position, {error}"))?;
+    Ok((state, to_move))
+}
+
+// the position and whether the first player is to move
+fn parse_position(text: &str) -> Result<((u64, u64), bool), String> {
+    if text.contains('/') {
+        return parse_grid(text);
+    }
+    if text == "-" {
+        return Ok(((0, 0), true));
+    }
+    let columns: Option<Vec<u32>> = text.chars().map(|c| c.to_digit(10)).collect();
+    columns
+        .and_then(|columns| Some((suite::replay(&columns)?, first_to_move(columns.len()))))
+        .ok_or_else(|| "invalid moves".to_string())
+}
+
+fn analyse(
+    text: &str,
+    time: u128,
+    params: &Params,
+    cache: &mut SolverCache,
+) -> Result<Analysis, String> {
+    let (state, first) = parse_position(text)?;
+    if is_over(state.0, state.1) {
+        return Err("the game is over".to_string());
+    }
+    let rules = Rules { swap: false };
+    let (eval, best, graph, chosen) = mcst(
+        init_graph(state, first),
+        0,
+        &Limits::movetime(time),
+        rules,
+        params,
+        cache,
+    );
+    let best = move_name(state, best);
+    // from the move chosen, which a proof can pick before it gets the most visits
+    let mut pv = vec![best.clone()];
+    pv.extend(principal_variation(&graph, chosen));
+    Ok(Analysis {
+        position: text.to_string(),
+        best,
+        eval,
+        pv,
+        visits: graph[0].nb_visit,
+    })
+}
+
+fn to_csv(analyses: &[Analysis]) -> String {
+    let mut lines = vec!["position,best,eval,pv,visits".to_string()];
+    for analysis in analyses {
+        lines.push(format!(
+            "{},{},{:.4},{},{}",
+            analysis.position,
+            analysis.best,
+            analysis.eval,
+            analysis.pv.concat(),
+            analysis.visits
+        ));
+    }
+    lines.join("\n") + "\n"
+}
+
+// an array with an object a position
+fn to_json(analyses: &[Analysis]) -> String {
+    let objects: Vec<String> = analyses
+        .iter()
+        .map(|analysis| {
+            let pv: Vec<String> = analysis.pv.iter().map(|name| json_string(name)).collect();
+            format!(
+                "{{\"position\":{},\"best\":{},\"eval\":{:.4},\"pv\":[{}],\"visits\":{}}}",
+                json_string(&analysis.position),
+                json_string(&analysis.best),
+                analysis.eval,
+                pv.join(","),
+                analysis.visits
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", objects.join(",\n"))
+}
+
+pub fn main(args: &[String]) {
+    let mut args = args.to_vec();
+    let json = match args.iter().position(|arg| arg == "--json") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+    let output = args.iter().position(|arg| arg == "--output").map(|i| {
+        args.remove(i);
+        if i >= args.len() {
+            usage_error("--output needs a file");
+        }
+        args.remove(i)
+    });
+    let path = args
+        .first()
+        .unwrap_or_else(|| usage_error("analyze-file needs a file"));
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|error| usage_error(&format!("can't read {path}: {error}")));
+    let time = match args.get(1) {
+        Some(time) => time
+            .parse()
+            .unwrap_or_else(|_| usage_error("the time must be a number")),
+        None => TIME_PER_MOVE,
+    };
+    let params = Params {
+        contempt: 0.0,
+        temperature: 0.0,
+        ..get_profile("default").unwrap()
+    };
+    let mut cache = SolverCache::new(tt::DEFAULT_MB);
+    let mut analyses = vec![];
+    for (n, line) in content.lines().enumerate() {
+        let text = line.trim();
+        if text.is_empty() {
+            continue;
+        }
+        match analyse(text, time, &params, &mut cache) {
+            Ok(analysis) => analyses.push(analysis),
+            Err(error) => eprintln!("{path}:{}: {text}: {error}", n + 1),
+        }
+    }
+    let text = if json {
+        to_json(&analyses)
+    } else {
+        to_csv(&analyses)
+    };
+    match output {
+        Some(output) => {
+            if let Err(error) = fs::write(&output, text) {
+                eprintln!("can't write {output}: {error}");
+            }
+        }
+        None => print!("{text}"),
+    }
+}
@@ -0,0 +1,727 @@
+// Text protocol for GUIs and match hosts, one command per line:
+//
+//     hello                  -> hello mcst_connect4
+//     id                     -> the lines below, ending with idok
+//     newgame [swap]         starts over, with the swap rule or not (and from the --seed)
+//     position [MOVE...]     the moves played from the empty board, "s" being the swap
+//     position board P1 P2 [MOVE...]
+//                            the moves played from another position, given as the bitboards
+//                            of the player to move and of the other one (bit 8 * row + column,
+//                            from the bottom left), which --strict checks with Board::validate
+//     setoption name NAME value VALUE
+//                            exploration, time (of a plain go) or playout, for the next searches
+//                            (the time scaled by --time-curve's factor for the ply)
+//     go [movetime MS]       -> bestmove MOVE eval EVAL
+//     go fresh ...           searches again a position already answered for that time, which
+//                            a plain go answers at once from memory, after `info cached`
+//     go infinite            searches in the background until `stop`
+//     go ... searchmoves MOVE...
+//                            only looks at these moves, in a tree of its own that the
+//                            next searches don't keep
+//     go ... excludemoves MOVE...
+//                            looks at every move but these, in the usual tree
+//     stop                   -> bestmove MOVE eval EVAL
+//     ponder [MOVE]          -> pondering MOVE
+//                            searches in the background on the opponent's turn, as if they
+//                            had played MOVE (when none is given, the reply the --player's
+//                            past games make the likeliest with --model-opponent, or else the
+//                            most visited one), except with the fast profile; it stops by
+//                            itself once the tree holds --max-memory's worth of nodes
+//     ponderhit              -> info ponder hits H misses M, then bestmove MOVE eval EVAL
+//                            they did: the move is played, and a plain go carries on from
+//                            the tree the pondering grew
+//     stop (when pondering)  -> info ponder hits H misses M
+//                            they didn't: back to the position before the move, whose tree
+//                            keeps what the pondering found
+//     tree                   -> what the searches found for the current position:
+//                               child MOVE visits V mean M status STATUS, for every move
+//                               pv MOVE...      (the most visited line)
+//                               nodes N         (in the tree below the position)
+//     quit
+//
+// a search that proves the position stops there, whatever its time, and its bestmove comes
+// after `info proof STATUS plies N`, N plies from the position to the end of the game.
+//
+// searches of LONG_SEARCH ms or more, infinite ones included, send a line on the way every
+// REPORT_EVERY iterations before the bestmove:
+//
+//     info iterations N visits V time MS best MOVE eval EVAL
+//
+// moves are columns 1 -> 7 (a -> g and full-width digits are read too, bestmove always
+// answers with digits), evaluations go from 0 (lost) to 2 (won) for the player who was to
+// move, and anything wrong is answered with `error <message>`. Another `go` on the same
+// position carries on from the statistics the previous searches left, once with another time
+// or with fresh: the answers of the session are kept by the position's hash and the time (up
+// to ANALYSES of them, and until an option changes), so that going back and forth between the
+// positions of an opening costs nothing.
+//
+// the statuses are proven results for the player making the move: won, lost, draw or
+// playing while unproven.
+//
+// the positions play out a game (see controller.rs), whose moves go to the hooks with the hooks
+// feature (see hooks.rs): a position that doesn't follow the last one, or a newgame, starts
+// another, the last one ending there (abandoned, when it wasn't over).
+//
+// `id` (and `mcst_connect4 --version`) tells what the engine is and what it can do:
+//
+//     id name mcst_connect4
+//     id version 0.1.0
+//     capability time movetime infinite
+//     capability variants standard swap
+//     capability rules first-four draw
+//     capability book none
+//     capability tablebase solver 12
+//     capability options exploration time playout
+//     idok
+//
+// the rules being the --scoring and --full-board ones (see rules.rs), and the tablebase the
+// exact solver, which takes over with that many empty cells left under the standard rules.
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    mem,
+    net::TcpStream,
+    sync::{Arc, Mutex},
+};
+
+use crate::{
+    board::{check_position, first_to_move, hash_state, is_over, parse_move},
+    controller::{ChildStats, GameController, GameResult},
+    dump::status_name,
+    expansion, get_node_moves, mcst, move_name, node_cap, players, proof_distance, random,
+    restricted_graph, rules,
+    search::{self, Position, SearchFuture, SearchResult},
+    set_option,
+    solver::SolverCache,
+    timing, usage_error, Limits, Node, Options, Params, Report, Rules, DEFAULT_MAX_MEMORY,
+    SETTINGS, TIME_PER_MOVE,
+};
+
+pub const ENGINE_NAME: &str = "mcst_connect4";
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+// the answers kept, forgotten all at once past that
+const ANALYSES: usize = 4096;
+
+// the reply to `id`, without the idok
+pub fn identity(params: &Params) -> Vec<String> {
+    // the solver only knows the standard rules
+    let solver_empty = if rules::is_standard() {
+        params.solver_empty
+    } else {
+        0
+    };
+    let tablebase = match solver_empty {
+        0 => "none".to_string(),
+        empty => format!("solver {empty}"),
+    };
+    vec![
+        format!("id name {ENGINE_NAME}"),
+        format!("id version {ENGINE_VERSION}"),
+        "capability time movetime infinite".to_string(),
+        "capability variants standard swap".to_string(),
+        format!("capability rules {}", rules::current().name()),
+        "capability book none".to_string(),
+        format!("capability tablebase {tablebase}"),
+        format!("capability options {}", SETTINGS.join(" ")),
+    ]
+}
+
+type InfoWriter = Arc<dyn Fn(&str) + Send + Sync>;
+// the position's hash, whether the swap is open and the time of the go
+type AnalysisKey = (u64, bool, u128);
+
+pub struct Engine {
+    rules: Rules,
+    params: Params,
+    // of a go without a time
+    time: u128,
+    // the game the positions given play out, with the tree and the table (kept from one game
+    // to the next)
+    game: GameController,
+    // a go infinite, which has the game's tree and table until it stops
+    search: Option<SearchFuture>,
+    // whether it searches a tree restricted to some moves, to leave behind once stopped
+    restricted: bool,
+    // the position after the move pondered on, played in the game if the opponent does
+    pondering: Option<(u64, u64)>,
+    // how often the move pondered on was the one played, and how often not
+    ponder_hits: u32,
+    ponder_misses: u32,
+    // where the info lines go, set by run
+    info: Option<InfoWriter>,
+    // the generator's, for every game
+    seed: Option<u64>,
+    // --model-opponent's player, whose past choices pick the move to ponder on
+    model: Option<players::Player>,
+    // the fast profile's: the searches stop at this many nodes, and there's no pondering
+    max_nodes: Option<usize>,
+    // where a ponder nobody stops or follows up ends, --max-memory's worth of nodes
+    ponder_nodes: usize,
+    // --time-curve's, for the gos without a time
+    curve: timing::Curve,
+    // --threads, for every search
+    threads: usize,
+    // the replies of the plain gos of the session
+    analyses: HashMap<AnalysisKey, String>,
+}
+
+impl Engine {
+    pub fn new(rules: Rules, params: Params, tt_mb: usize) -> Engine {
+        let mut game = GameController::new(rules, params, TIME_PER_MOVE, tt_mb);
+        let (_, _, cache) = game.parts();
+        Engine {
+            rules,
+            params,
+            time: TIME_PER_MOVE,
+            ponder_nodes: node_cap(DEFAULT_MAX_MEMORY, cache),
+            game,
+            search: None,
+            restricted: false,
+            pondering: None,
+            ponder_hits: 0,
+            ponder_misses: 0,
+            info: None,
+            seed: None,
+            model: None,
+            max_nodes: None,
+            curve: timing::Curve::default(),
+            threads: 1,
+            analyses: HashMap::new(),
+        }
+    }
+
+    // the engine of `protocol` and `connect`, a plain go searching to --iterations if given
+    fn from_options(options: &Options) -> Engine {
+        let mut engine = Engine::new(options.rules, options.params, options.tt_mb);
+        engine.seed = options.seed;
+        if options.model_opponent {
+            // parse_options made sure there is a player
+            let name = options.player.as_ref().unwrap();
+            let known =
+                players::load(&options.players_file).unwrap_or_else(|error| usage_error(&error));
+            engine.model = known.into_iter().find(|player| player.name == *name);
+        }
+        if options.iterations.is_some() {
+            engine.time = u128::MAX;
+        }
+        engine.curve = options.time_curve.clone();
+        engine.threads = options.threads;
+        let (_, _, cache) = engine.game.parts();
+        engine.ponder_nodes = node_cap(options.max_memory_mb, cache);
+        if options.fast {
+            engine.max_nodes = Some(engine.ponder_nodes);
+        }
+        engine.new_game();
+        engine
+    }
+
+    fn new_game(&mut self) {
+        if let Some(seed) = self.seed {
+            random::seed(seed);
+        }
+        self.reset();
+    }
+
+    // another game from the empty board, with the table of the one it replaces
+    fn reset(&mut self) {
+        self.reset_to((0, 0));
+    }
+
+    fn reset_to(&mut self, state: (u64, u64)) {
+        let (_, _, cache) = self.game.parts();
+        let cache = mem::replace(cache, SolverCache::new(0));
+        self.game = GameController::new(self.rules, self.params, self.time, 0)
+            .with_cache(cache)
+            .starting_from(state, None);
+    }
+
+    // the position after a move of the game's
+    fn parse(&self, text: &str) -> Result<(u64, u64), String> {
+        if self.game.result() != GameResult::Playing {
+            return Err(format!("the game is over before {text}"));
+        }
+        parse_move(self.game.state(), self.game.can_swap(), text)
+            .map_err(|error| format!("illegal move {text}: {error}"))
+    }
+
+    fn play(&mut self, text: &str) -> Result<(), String> {
+        let state = self.parse(text)?;
+        self.game.play(state, None)
+    }
+
+    // keeps the game, and its tree, when the new position follows the current one
+    fn set_position(&mut self, words: &[&str]) -> Result<(), String> {
+        let moves = match words {
+            ["board", p1, p2, moves @ ..] => {
+                let bitboard = |text: &str| {
+                    text.parse::<u64>()
+                        .map_err(|_| format!("invalid bitboard: {text}"))
+                };
+                let state = (bitboard(p1)?, bitboard(p2)?);
+                // nothing else checks a position that no move led to
+                check_position(state)?;
+                self.reset_to(state);
+                moves
+            }
+            moves if self.follows(moves) => moves,
+            moves => {
+                self.reset();
+                moves
+            }
+        };
+        for text in &moves[self.game.moves().len()..] {
+            if let Err(error) = self.play(text) {
+                self.reset();
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    // whether the moves start with the game's, as the record has them whichever way they were
+    // typed
+    fn follows(&self, moves: &[&str]) -> bool {
+        let played = self.game.moves();
+        let mut state = (0, 0);
+        let same = moves.len() >= played.len()
+            && played
+                .iter()
+                .zip(moves)
+                .enumerate()
+                .all(|(i, (name, text))| {
+                    let Ok(next) = parse_move(state, self.rules.swap && i == 1, text) else {
+                        return false;
+                    };
+                    let same = move_name(state, next) == *name;
+                    state = next;
+                    same
+                });
+        // and that the game started from the empty board too, not from a `position board`
+        same && state == self.game.state()
+    }
+
+    // the root's moves, none while a go infinite has the tree
+    pub fn root_children(&self) -> impl Iterator<Item = ChildStats> + '_ {
+        let tree = (!self.game.graph().is_empty()).then_some(&self.game);
+        tree.into_iter().flat_map(GameController::root_children)
+    }
+
+    // the most visited line from the current position
+    pub fn pv(&self) -> Vec<String> {
+        if self.game.graph().is_empty() {
+            return vec![];
+        }
+        self.game.pv()
+    }
+
+    // the nodes below the current position, itself included
+    pub fn node_count(&self) -> usize {
+        if self.game.graph().is_empty() {
+            return 0;
+        }
+        self.game.node_count()
+    }
+
+    fn tree(&self) -> String {
+        let mut lines: Vec<String> = self
+            .root_children()
+            .map(|child| {
+                format!(
+                    "child {} visits {} mean {:.4} status {}",
+                    child.name,
+                    child.visits,
+                    child.mean,
+                    status_name(&child.status)
+                )
+            })
+            .collect();
+        lines.push(format!("pv {}", self.pv().join(" ")).trim_end().to_string());
+        lines.push(format!("nodes {}", self.node_count()));
+        lines.join("\n")
+    }
+
+    // the states after the moves, without repeats
+    fn parse_moves(&self, texts: &[&str]) -> Result<Vec<(u64, u64)>, String> {
+        let (state, can_swap) = (self.game.state(), self.game.can_swap());
+        let mut moves = vec![];
+        for text in texts {
+            let state = parse_move(state, can_swap, text)
+                .map_err(|error| format!("illegal move {text}: {error}"))?;
+            if !moves.contains(&state) {
+                moves.push(state);
+            }
+        }
+        Ok(moves)
+    }
+
+    // the tree ready for a search and its root, a new one when the search is restricted
+    fn prepare(&mut self, searchmoves: &[&str]) -> Result<(Vec<Node>, usize), String> {
+        if self.game.result() != GameResult::Playing {
+            return Err("the game is over".to_string());
+        }
+        if !searchmoves.is_empty() {
+            let (state, can_swap) = (self.game.state(), self.game.can_swap());
+            let first = first_to_move(self.game.moves().len());
+            let moves = self.parse_moves(searchmoves)?;
+            let (_, _, cache) = self.game.parts();
+            let graph = restricted_graph(
+                state,
+                first,
+                can_swap,
+                moves,
+                self.rules,
+                &self.params,
+                cache,
+            );
+            return Ok((graph, 0));
+        }
+        // the moves played since the last search left some of the tree behind
+        self.game.compact();
+        let (graph, root, _) = self.game.parts();
+        Ok((mem::take(graph), root))
+    }
+
+    // for a search of `state`, the reports naming its moves
+    fn limits(&self, time: u128, state: (u64, u64)) -> Limits {
+        let limits = Limits::movetime(time)
+            .capped(self.max_nodes)
+            .with_threads(self.threads);
+        let Some(info) = self.info.clone() else {
+            return limits;
+        };
+        limits.with_reports(Arc::new(move |report: &Report| {
+            info(&format!(
+                "info iterations {} visits {} time {} best {} eval {:.4}",
+                report.iterations,
+                report.visits,
+                report.time,
+                move_name(state, report.best),
+                report.score
+            ))
+        }))
+    }
+
+    // a restricted tree isn't kept
+    fn best_move(
+        &mut self,
+        score: f64,
+        state: (u64, u64),
+        graph: Vec<Node>,
+        root: usize,
+        keep: bool,
+    ) -> String {
+        let best = format!(
+            "bestmove {} eval {score:.4}",
+            move_name(self.game.state(), state)
+        );
+        let (tree, _, cache) = self.game.parts();
+        let proof = proof_distance(&graph, root, &self.params, cache).map(|plies| {
+            let status = status_name(&graph[root].status);
+            format!("info proof {status} plies {plies}\n")
+        });
+        if keep {
+            *tree = graph;
+        }
+        format!("{}{best}", proof.unwrap_or_default())
+    }
+
+    fn go(
+        &mut self,
+        time: u128,
+        searchmoves: &[&str],
+        excluded: &[&str],
+    ) -> Result<String, String> {
+        let excluded = self.parse_moves(excluded)?;
+        let limits = self.limits(time, self.game.state()).excluding(excluded);
+        let (graph, root) = self.prepare(searchmoves)?;
+        let (_, _, cache) = self.game.parts();
+        let (score, state, graph, _) = mcst(graph, root, &limits, self.rules, &self.params, cache);
+        Ok(self.best_move(score, state, graph, root, searchmoves.is_empty()))
+    }
+
+    fn go_infinite(&mut self, searchmoves: &[&str], excluded: &[&str]) -> Result<(), String> {
+        let excluded = self.parse_moves(excluded)?;
+        let limits = self
+            .limits(u128::MAX, self.game.state())
+            .excluding(excluded);
+        let (graph, root) = self.prepare(searchmoves)?;
+        let (_, _, cache) = self.game.parts();
+        let position = Position {
+            graph,
+            root,
+            rules: self.rules,
+            params: self.params,
+            cache: mem::replace(cache, SolverCache::new(0)),
+        };
+        self.search = Some(search::search(position, limits));
+        self.restricted = !searchmoves.is_empty();
+        Ok(())
+    }
+
+    // the search's result, its table back with the game
+    fn halt(&mut self) -> Result<SearchResult, String> {
+        let search = self.search.take().ok_or("no search to stop")?;
+        search.cancel();
+        let mut result = search.wait();
+        let (_, _, cache) = self.game.parts();
+        *cache = mem::replace(&mut result.position.cache, SolverCache::new(0));
+        Ok(result)
+    }
+
+    fn stop(&mut self) -> Result<String, String> {
+        if self.pondering.is_some() {
+            return self.ponder_miss();
+        }
+        let result = self.halt()?;
+        let keep = !self.restricted;
+        let root = result.position.root;
+        Ok(self.best_move(result.score, result.best, result.position.graph, root, keep))
+    }
+
+    // searches the position after the opponent's expected move, in the game's tree whose root
+    // stays where it is until they play it
+    fn ponder(&mut self, text: Option<&str>) -> Result<String, String> {
+        if self.max_nodes.is_some() {
+            return Err("no pondering with the fast profile".to_string());
+        }
+        let moves: Vec<String> = self
+            .game
+            .moves()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let guess = self.model.as_ref().and_then(|model| model.predict(&moves));
+        let text = match text.or(guess) {
+            Some(text) => text.to_string(),
+            None => {
+                let reply = self
+                    .game
+                    .root_children()
+                    .max_by_key(|child| child.visits)
+                    .ok_or("no move to ponder on, search first")?;
+                reply.name
+            }
+        };
+        let state = self.parse(&text)?;
+        if is_over(state.0, state.1) {
+            return Err(format!("the game is over after {text}"));
+        }
+        let (graph, root, cache) = self.game.parts();
+        if graph[root].nb_children == 0 {
+            let moves = get_node_moves(&graph[root]);
+            expansion(root, graph, moves, self.rules, &self.params, cache);
+        }
+        let child = graph[root]
+            .children()
+            .find(|child| graph[*child].state == state)
+            .unwrap();
+        let position = Position {
+            graph: mem::take(graph),
+            root: child,
+            rules: self.rules,
+            params: self.params,
+            cache: mem::replace(cache, SolverCache::new(0)),
+        };
+        let limits = self
+            .limits(u128::MAX, state)
+            .capped(Some(self.ponder_nodes));
+        self.search = Some(search::search(position, limits));
+        self.restricted = false;
+        self.pondering = Some(state);
+        Ok(format!("pondering {text}"))
+    }
+
+    fn ponder_stats(&self) -> String {
+        format!(
+            "info ponder hits {} misses {}",
+            self.ponder_hits, self.ponder_misses
+        )
+    }
+
+    fn ponder_hit(&mut self) -> Result<String, String> {
+        let state = self.pondering.take().ok_or("not pondering")?;
+        let result = self.halt()?;
+        let (graph, _, _) = self.game.parts();
+        *graph = result.position.graph;
+        self.game.play(state, None)?;
+        self.ponder_hits += 1;
+        let stats = self.ponder_stats();
+        let best = self.go(self.time, &[], &[])?;
+        Ok(format!("{stats}\n{best}"))
+    }
+
+    fn ponder_miss(&mut self) -> Result<String, String> {
+        self.pondering.take().ok_or("not pondering")?;
+        let result = self.halt()?;
+        let (graph, _, _) = self.game.parts();
+        *graph = result.position.graph;
+        self.ponder_misses += 1;
+        Ok(self.ponder_stats())
+    }
+
+    // a plain go, answered from memory unless fresh
+    fn go_cached(&mut self, time: u128, fresh: bool) -> Result<String, String> {
+        let (p1, p2) = self.game.state();
+        let key = (hash_state(p1, p2), self.game.can_swap(), time);
+        if let Some(reply) = self.analyses.get(&key).filter(|_| !fresh) {
+            return Ok(format!("info cached\n{reply}"));
+        }
+        let reply = self.go(time, &[], &[])?;
+        if self.analyses.len() >= ANALYSES {
+            self.analyses.clear();
+        }
+        self.analyses.insert(key, reply.clone());
+        Ok(reply)
+    }
+
+    // go [fresh] [movetime MS | infinite] [searchmoves MOVE... | excludemoves MOVE...]
+    fn start_go(&mut self, words: &[&str]) -> Result<Option<String>, String> {
+        let (fresh, words) = match words {
+            ["fresh", rest @ ..] => (true, rest),
+            rest => (false, rest),
+        };
+        let (time, rest) = match words {
+            ["infinite", rest @ ..] => (None, rest),
+            ["movetime", time, rest @ ..] => match time.parse() {
+                Ok(time) => (Some(time), rest),
+                Err(_) => return Err(format!("invalid time: {time}")),
+            },
+            rest => {
+                let ply = self.game.moves().len() as u32 + 1;
+                (Some(self.curve.time(self.time, ply)), rest)
+            }
+        };
+        let (searchmoves, excluded) = match rest {
+            [] => (&[][..], &[][..]),
+            ["searchmoves", moves @ ..] if !moves.is_empty() => (moves, &[][..]),
+            ["excludemoves", moves @ ..] if !moves.is_empty() => (&[][..], moves),
+            _ => return Err(format!("unknown go arguments: {}", words.join(" "))),
+        };
+        match time {
+            Some(time) if rest.is_empty() => self.go_cached(time, fresh).map(Some),
+            Some(time) => self.go(time, searchmoves, excluded).map(Some),
+            None => self.go_infinite(searchmoves, excluded).map(|_| None),
+        }
+    }
+
+    // the reply to a command, if any; Err(None) means quit
+    pub fn handle(&mut self, line: &str) -> Result<Option<String>, Option<String>> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if self.search.is_some() {
+            // the tree belongs to the search until it stops
+            return match words[..] {
+                [] => Ok(None),
+                ["stop"] => self.stop().map(Some).map_err(Some),
+                ["ponderhit"] => self.ponder_hit().map(Some).map_err(Some),
+                ["quit"] => {
+                    self.stop().map_err(Some)?;
+                    Err(None)
+                }
+                _ => Err(Some(format!("searching, stop first: {line}"))),
+            };
+        }
+        match words[..] {
+            [] => Ok(None),
+            ["hello"] => Ok(Some(format!("hello {ENGINE_NAME}"))),
+            ["id"] => {
+                let mut lines = identity(&self.params);
+                lines.push("idok".to_string());
+                Ok(Some(lines.join("\n")))
+            }
+            ["newgame"] | ["newgame", "swap"] => {
+                self.rules = Rules {
+                    swap: words.len() == 2,
+                };
+                self.new_game();
+                Ok(None)
+            }
+            ["position", ..] => self.set_position(&words[1..]).map(|_| None).map_err(Some),
+            ["setoption", "name", name, "value", value] => {
+                // the answers kept were searched with the old value
+                self.analyses.clear();
+                set_option(&mut self.params, &mut self.time, name, value)
+                    .map(|_| None)
+                    .map_err(Some)
+            }
+            ["go", ..] => self.start_go(&words[1..]).map_err(Some),
+            ["stop"] => self.stop().map(Some).map_err(Some),
+            ["ponder"] => self.ponder(None).map(Some).map_err(Some),
+            ["ponder", text] => self.ponder(Some(text)).map(Some).map_err(Some),
+            ["ponderhit"] => self.ponder_hit().map(Some).map_err(Some),
+            ["tree"] => Ok(Some(self.tree())),
+            ["quit"] => Err(None),
+            _ => Err(Some(format!("unknown command: {line}"))),
+        }
+    }
+}
+
+pub fn run(
+    input: impl BufRead,
+    output: impl Write + Send + 'static,
+    engine: &mut Engine,
+) -> io::Result<()> {
+    // shared with the searches running in the background
+    let output = Arc::new(Mutex::new(output));
+    let info_output = output.clone();
+    engine.info = Some(Arc::new(move |line: &str| {
+        let mut output = info_output.lock().unwrap();
+        // a host that went away will be noticed by the main loop
+        let _ = writeln!(output, "{line}").and_then(|_| output.flush());
+    }));
+    for line in input.lines() {
+        let reply = match engine.handle(&line?) {
+            Ok(None) => continue,
+            Ok(Some(reply)) => reply,
+            Err(Some(error)) => format!("error {error}"),
+            Err(None) => break,
+        };
+        let mut output = output.lock().unwrap();
+        writeln!(output, "{reply}")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+// `protocol [OPTIONS]`, over stdin and stdout
+pub fn main(options: Options) {
+    let mut engine = Engine::from_options(&options);
+    if let Err(error) = run(io::stdin().lock(), io::stdout(), &mut engine) {
+        eprintln!("{error}");
+    }
+}
+
+// `connect ADDR [OPTIONS]`, to play on a match host
+pub fn connect(addr: &str, options: Options) {
+    let stream = TcpStream::connect(addr)
+        .unwrap_or_else(|error| usage_error(&format!("can't connect to {addr}: {error}")));
+    let input = BufReader::new(stream.try_clone().expect("Failed to clone the connection"));
+    let mut engine = Engine::from_options(&options);
+    if let Err(error) = run(input, stream, &mut engine) {
+        eprintln!("{error}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{board::set_strict, get_profile};
+
+    #[test]
+    fn strict_mode_rejects_impossible_boards() {
+        set_strict(true);
+        let mut engine = Engine::new(Rules { swap: false }, get_profile("default").unwrap(), 0);
+        // both players on the bottom left cell
+        let error = engine.handle("position board 1 1").unwrap_err().unwrap();
+        assert!(error.starts_with("impossible position"), "{error}");
+        // the player to move a disc ahead
+        assert!(engine.handle("position board 1 0").is_err());
+        assert!(engine.handle("position board x 0").is_err());
+        // the other one's disc in the fourth column, and a move on top of it
+        assert_eq!(engine.handle("position board 0 8 4"), Ok(None));
+        assert_eq!(engine.game.state(), (8, 8 << 8));
+        assert_eq!(engine.game.moves().len(), 1);
+        // moves from the empty board start another game
+        assert_eq!(engine.handle("position 4"), Ok(None));
+        assert_eq!(engine.game.state(), (0, 8));
+    }
+}
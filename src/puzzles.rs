@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+
+use rand::Rng;
+
+use crate::{
+    advance_root,
+    board::{first_to_move, get_moves, is_over, is_winning, show_grid, Board},
+    get_profile, get_user_move, init_graph, mcst, scale,
+    solver::{self, SolverCache},
+    suite,
+    theme::Theme,
+    to_user_move, tt, usage_error, Limits, Rules,
+};
+
+const SELF_PLAY_TIME: u128 = 100; // milliseconds
+const FEEDBACK_TIME: u128 = 500; // milliseconds
+                                 // opening moves played at random so the games don't all look the same
+const RANDOM_PLIES: usize = 4;
+const DEFAULT_FILE: &str = "puzzles.txt";
+
+pub fn main(args: &[String]) {
+    let path = |i: usize| args.get(i).map_or(DEFAULT_FILE, String::as_str);
+    match args.first().map(String::as_str) {
+        Some("generate") => {
+            let count = match args.get(1) {
+                Some(count) => count
+                    .parse()
+                    .unwrap_or_else(|_| usage_error("the puzzle count must be a number")),
+                None => 10,
+            };
+            generate(count, path(2));
+        }
+        Some("play") => play(path(1)),
+        _ => usage_error("unknown puzzles command"),
+    }
+}
+
+fn self_play(cache: &mut SolverCache) -> Vec<u32> {
+    let rules = Rules { swap: false };
+    let params = get_profile("default").unwrap();
+    let mut state = (0, 0);
+    let mut graph = init_graph(state, true);
+    let mut root = 0;
+    let mut moves = vec![];
+    while !is_over(state.0, state.1) {
+        let previous_state = state;
+        if moves.len() < RANDOM_PLIES {
+            let options = get_moves(state.0, state.1);
+            state = options[rand::thread_rng().gen_range(0..options.len())];
+            root = advance_root(&mut graph, root, state, rules);
+        } else {
+            (_, state, graph, root) = mcst(
+                graph,
+                root,
+                &Limits::movetime(SELF_PLAY_TIME),
+                rules,
+                &params,
+                cache,
+            );
+        }
+        moves.push(to_user_move(previous_state, state));
+    }
+    moves
+}
+
+// the only move forcing a win in the fewest plies, with that number of plies;
+// immediate wins are too easy to be worth a puzzle
+fn unique_win(p1: u64, p2: u64) -> Option<(u32, u32)> {
+    let moves = get_moves(p1, p2);
+    if moves.iter().any(|(_, player)| is_winning(*player)) {
+        return None;
+    }
+    for plies in [3, 5, 7] {
+        let winning: Vec<u32> = moves
+            .iter()
+            .filter(|(q1, q2)| solver::loses_within(*q1, *q2, plies - 1))
+            .map(|state| to_user_move((p1, p2), *state))
+            .collect();
+        match winning[..] {
+            [] => continue,
+            [column] => return Some((column, plies)),
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn generate(count: usize, path: &str) {
+    let mut seen = HashSet::new();
+    let mut cache = SolverCache::new(tt::DEFAULT_MB);
+    let mut puzzles = vec![];
+    let mut games = 0;
+    while puzzles.len() < count && games < count * 50 {
+        games += 1;
+        let game = self_play(&mut cache);
+        let mut board = Board::new(0, 0);
+        for i in 0..game.len() {
+            if i > 0 {
+                board = board.play(game[i - 1]).unwrap();
+            }
+            // the same position often comes back from one game to the next
+            if i < RANDOM_PLIES || !seen.insert(board.hash()) {
+                continue;
+            }
+            let (p1, p2) = board.state();
+            if let Some((best, plies)) = unique_win(p1, p2) {
+                println!("found: {} bm {best}", suite::moves_to_string(&game[..i]));
+                puzzles.push(suite::Entry {
+                    moves: game[..i].to_vec(),
+                    best,
+                    comment: format!("wins in {plies} plies"),
+                });
+                if puzzles.len() == count {
+                    break;
+                }
+            }
+        }
+    }
+    println!("{} puzzles from {games} games", puzzles.len());
+    if let Err(error) = suite::save(path, &puzzles) {
+        usage_error(&error);
+    }
+    println!("saved to {path}");
+}
+
+// what the engine would answer to a wrong move, `first` when the engine is the first player
+fn feedback(state: (u64, u64), first: bool) {
+    let rules = Rules { swap: false };
+    let params = get_profile("default").unwrap();
+    if is_over(state.0, state.1) {
+        println!("that ends the game, but doesn't win it");
+        return;
+    }
+    let (score, reply, _, _) = mcst(
+        init_graph(state, first),
+        0,
+        &Limits::movetime(FEEDBACK_TIME),
+        rules,
+        &params,
+        &mut SolverCache::new(1),
+    );
+    let column = to_user_move(state, reply);
+    if is_winning(reply.1) {
+        println!("that lets me win with {column}");
+    } else {
+        println!(
+            "that doesn't win, I would answer {column} (evaluation for me: {})",
+            scale::percent(score)
+        );
+    }
+}
+
+fn play(path: &str) {
+    let puzzles = suite::load(path).unwrap_or_else(|error| usage_error(&error));
+    let theme = Theme::plain();
+    let mut solved = 0;
+    for (n, puzzle) in puzzles.iter().enumerate() {
+        let (p1, p2) = suite::replay(&puzzle.moves).unwrap();
+        let first = first_to_move(puzzle.moves.len());
+        println!(
+            "puzzle {}/{}: {} to move and win ({})",
+            n + 1,
+            puzzles.len(),
+            theme.symbols.of(first).0,
+            puzzle.comment
+        );
+        show_grid(p1, p2, &theme, first);
+        let mut found = false;
+        for _ in 0..2 {
+            let state = get_user_move(p1, p2, false);
+            if to_user_move((p1, p2), state) == puzzle.best {
+                found = true;
+                break;
+            }
+            feedback(state, !first);
+        }
+        if found {
+            println!("correct!");
+            solved += 1;
+        } else {
+            println!("the winning move was {}", puzzle.best);
+        }
+    }
+    println!("solved {solved} out of {}", puzzles.len());
+}
@@ -0,0 +1,28 @@
+use std::cell::RefCell;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// the searches draw from one generator per thread, seeding it makes them repeatable: the threads
+// a search starts (search.rs in the background, the workers of --threads) get theirs seeded from
+// the starting thread's, so a seed and a thread count always give the same streams
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::from_entropy());
+}
+
+pub fn seed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// from 0 to n excluded
+pub fn below(n: usize) -> usize {
+    RNG.with(|rng| rng.borrow_mut().gen_range(0..n))
+}
+
+pub fn bits() -> u64 {
+    RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+// from 0 to max excluded
+pub fn uniform(max: f64) -> f64 {
+    RNG.with(|rng| rng.borrow_mut().gen_range(0.0..max))
+}
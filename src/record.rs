@@ -0,0 +1,455 @@
+// Game records, in a tagged format borrowed from chess' PGN:
+//
+//     [Date "2026.10.14"]
+//     [First "alice"]
+//     [Second "mcst_connect4 default 1000ms"]
+//     [Rules "standard"]
+//     [Result "0-1"]
+//
+//     1. 4 4 {eval 1.0712} 2. 3 5 {eval 1.1034} 3. 2 6 {eval 1.9400} 4. 1 0-1
+//
+// moves are columns 1 -> 7 or "s" for the swap, each of them may be followed by a
+// {comment}, and the result is 1-0 when the first player won, 0-1 when the second one did,
+// 1/2-1/2 for a draw and * for a game that didn't finish. Rules is "standard" or "swap", and
+// a Setup tag such as "first 4" gives one player an extra disc at the bottom of a column
+// before the first move. A Symbols tag ("OX") says what the first and the second player's
+// discs were drawn with when it wasn't X and O, and a Variant tag ("most-fours draw") what
+// ended the game and who won it when those weren't the standard rules (see rules.rs). The
+// engine's games against a human end with what its searches added up to:
+//
+//     [Engine "searches 21 playouts 1203345 nodes 1187002 time 21040 depth 9.41"]
+//
+// the time in milliseconds and the depth the average of the positions played out, counted
+// from the searched position. A file holds any number of games one after the other.
+//
+// show-record FILE [GAME]: lists the games of a file, or replays one of them.
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    board::{parse_move, show_grid, Symbols},
+    rules,
+    theme::Theme,
+    usage_error, Rules,
+};
+
+const LINE_WIDTH: usize = 80;
+
+pub struct Record {
+    pub tags: Vec<(String, String)>,
+    // each with the comment following it
+    pub moves: Vec<(String, Option<String>)>,
+}
+
+// days since 1970-01-01 to year, month and day, after Howard Hinnant's civil_from_days
+fn civil_date(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+pub fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let (year, month, day) = civil_date(seconds as i64 / 86_400);
+    format!("{year}.{month:02}.{day:02}")
+}
+
+// the Result tag for a finished game, `winner` being 0 for the first player
+pub fn result_text(winner: Option<usize>) -> &'static str {
+    match winner {
+        Some(0) => "1-0",
+        Some(_) => "0-1",
+        None => "1/2-1/2",
+    }
+}
+
+impl Record {
+    pub fn new(first: &str, second: &str, rules: Rules) -> Record {
+        let mut record = Record {
+            tags: vec![
+                ("Date".to_string(), today()),
+                ("First".to_string(), first.to_string()),
+                ("Second".to_string(), second.to_string()),
+                (
+                    "Rules".to_string(),
+                    if rules.swap { "swap" } else { "standard" }.to_string(),
+                ),
+                ("Result".to_string(), "*".to_string()),
+            ],
+            moves: vec![],
+        };
+        let variant = rules::current();
+        if variant != rules::STANDARD {
+            record.set_tag("Variant", &variant.name());
+        }
+        record
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(key, _)| key == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn push(&mut self, text: &str, comment: Option<String>) {
+        self.moves.push((text.to_string(), comment));
+    }
+
+    pub fn rules(&self) -> Rules {
+        Rules {
+            swap: self.tag("Rules") == Some("swap"),
+        }
+    }
+
+    // the position before the first move
+    pub fn start(&self) -> Result<(u64, u64), String> {
+        let Some(setup) = self.tag("Setup") else {
+            return Ok((0, 0));
+        };
+        let disc = |column: &str| match column.parse::<u32>() {
+            Ok(column) if (1..=7).contains(&column) => Ok(1 << (column - 1)),
+            _ => Err(format!("invalid setup: {setup}")),
+        };
+        // the first player is to move and owns p1
+        match setup.split_whitespace().collect::<Vec<&str>>()[..] {
+            ["first", column] => Ok((disc(column)?, 0)),
+            ["second", column] => Ok((0, disc(column)?)),
+            _ => Err(format!("invalid setup: {setup}")),
+        }
+    }
+
+    // the standard rules without a Variant tag
+    pub fn variant(&self) -> Result<rules::Variant, String> {
+        let Some(variant) = self.tag("Variant") else {
+            return Ok(rules::STANDARD);
+        };
+        let invalid = || format!("invalid variant: {variant}");
+        let [scoring, full_board] = variant.split_whitespace().collect::<Vec<&str>>()[..] else {
+            return Err(invalid());
+        };
+        let find = |name| rules::SCORINGS.iter().find(|(entry, _)| *entry == name);
+        let (_, scoring) = find(scoring).ok_or_else(invalid)?;
+        let find = |name| rules::FULL_BOARDS.iter().find(|(entry, _)| *entry == name);
+        let (_, full_board) = find(full_board).ok_or_else(invalid)?;
+        Ok(rules::Variant {
+            scoring: *scoring,
+            full_board: *full_board,
+        })
+    }
+
+    // the positions from the start to after the last move, every move checked under the game's
+    // rules (the process' staying as they are)
+    pub fn replay(&self) -> Result<Vec<(u64, u64)>, String> {
+        let variant = self.variant()?;
+        let rules = self.rules();
+        let mut states = vec![self.start()?];
+        for (i, (text, _)) in self.moves.iter().enumerate() {
+            let state = *states.last().unwrap();
+            if variant.is_over(state.0, state.1) {
+                return Err(format!("move {} comes after the end of the game", i + 1));
+            }
+            let can_swap = rules.swap && i == 1;
+            let next = parse_move(state, can_swap, text)
+                .map_err(|error| format!("illegal move {} at ply {}: {error}", text, i + 1))?;
+            states.push(next);
+        }
+        Ok(states)
+    }
+
+    pub fn format(&self) -> String {
+        let mut text: String = self
+            .tags
+            .iter()
+            .map(|(key, value)| format!("[{key} \"{value}\"]\n"))
+            .collect();
+        text.push('\n');
+        let mut tokens = vec![];
+        for (i, (name, comment)) in self.moves.iter().enumerate() {
+            if i % 2 == 0 {
+                tokens.push(format!("{}.", i / 2 + 1));
+            }
+            tokens.push(name.clone());
+            if let Some(comment) = comment {
+                tokens.push(format!("{{{comment}}}"));
+            }
+        }
+        tokens.push(self.tag("Result").unwrap_or("*").to_string());
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > LINE_WIDTH {
+                text += &line;
+                text.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line += &token;
+        }
+        text += &line;
+        text.push('\n');
+        text
+    }
+}
+
+fn is_result(token: &str) -> bool {
+    ["1-0", "0-1", "1/2-1/2", "*"].contains(&token)
+}
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (key, value) = inner.split_once(' ')?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((key.to_string(), value.to_string()))
+}
+
+pub fn parse(text: &str) -> Result<Vec<Record>, String> {
+    let mut records = vec![];
+    let mut record = Record {
+        tags: vec![],
+        moves: vec![],
+    };
+    let mut started = false;
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '[' => {
+                let mut tag = String::from('[');
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                    tag.push(c);
+                }
+                line += 1;
+                let tag = parse_tag(tag.trim_end())
+                    .ok_or_else(|| format!("line {}: invalid tag", line - 1))?;
+                record.tags.push(tag);
+                started = true;
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => {
+                            line += (c == '\n') as usize;
+                            comment.push(c);
+                        }
+                        None => return Err(format!("line {line}: unclosed comment")),
+                    }
+                }
+                match record.moves.last_mut() {
+                    Some((_, slot)) => *slot = Some(comment.trim().to_string()),
+                    None => return Err(format!("line {line}: comment before the first move")),
+                }
+            }
+            c => {
+                let mut token = String::from(c);
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '{' {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                started = true;
+                if is_result(&token) {
+                    if record.tag("Result").is_none() {
+                        record.set_tag("Result", &token);
+                    }
+                    records.push(record);
+                    record = Record {
+                        tags: vec![],
+                        moves: vec![],
+                    };
+                    started = false;
+                } else if !token.ends_with('.') {
+                    record.moves.push((token, None));
+                }
+            }
+        }
+    }
+    if started {
+        return Err("the last game has no result".to_string());
+    }
+    for (i, record) in records.iter().enumerate() {
+        record
+            .replay()
+            .map_err(|error| format!("game {}: {error}", i + 1))?;
+    }
+    Ok(records)
+}
+
+pub fn load(path: &str) -> Result<Vec<Record>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}"))?;
+    parse(&content).map_err(|error| format!("{path}: {error}"))
+}
+
+// games go one after the other, a blank line apart
+pub fn append(path: &str, record: &Record) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|error| format!("can't write {path}: {error}"))?;
+    writeln!(file, "{}", record.format()).map_err(|error| format!("can't write {path}: {error}"))
+}
+
+pub fn main(args: &[String]) {
+    let path = args
+        .first()
+        .unwrap_or_else(|| usage_error("show-record needs a file"));
+    let records = load(path).unwrap_or_else(|error| usage_error(&error));
+    let Some(game) = args.get(1) else {
+        for (i, record) in records.iter().enumerate() {
+            println!(
+                "{}: {} - {} {}, {} moves, {}",
+                i + 1,
+                record.tag("First").unwrap_or("?"),
+                record.tag("Second").unwrap_or("?"),
+                record.tag("Result").unwrap_or("*"),
+                record.moves.len(),
+                record.tag("Date").unwrap_or("no date")
+            );
+        }
+        return;
+    };
+    let record = game
+        .parse::<usize>()
+        .ok()
+        .and_then(|game| records.get(game.wrapping_sub(1)))
+        .unwrap_or_else(|| usage_error(&format!("no game {game} in {path}")));
+    for (key, value) in &record.tags {
+        println!("{key}: {value}");
+    }
+    let states = record.replay().unwrap();
+    for (i, (text, comment)) in record.moves.iter().enumerate() {
+        let comment = comment
+            .as_ref()
+            .map_or(String::new(), |c| format!(" ({c})"));
+        println!("{}. {text}{comment}", i + 1);
+    }
+    let (p1, p2) = *states.last().unwrap();
+    let mut theme = Theme::plain();
+    if let Some(symbols) = record.tag("Symbols").and_then(Symbols::parse) {
+        theme.symbols = symbols;
+    }
+    show_grid(p1, p2, &theme, record.moves.len() % 2 == 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(tags: &[(&str, &str)], moves: &[(&str, Option<&str>)]) -> Record {
+        Record {
+            tags: tags
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            moves: moves
+                .iter()
+                .map(|(text, comment)| (text.to_string(), comment.map(str::to_string)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn format_and_parse_round_trip() {
+        let swapped = record(
+            &[
+                ("First", "alice"),
+                ("Second", "mcst_connect4 default 1000ms"),
+                ("Rules", "swap"),
+                ("Variant", "most-fours second"),
+                ("Result", "*"),
+            ],
+            &[
+                ("4", None),
+                ("s", Some("eval 1.0712")),
+                ("3", Some("a comment of a few words")),
+                ("4", None),
+            ],
+        );
+        let standard = record(
+            &[
+                ("Rules", "standard"),
+                ("Setup", "second 1"),
+                ("Result", "1-0"),
+            ],
+            &[("4", Some("eval 1.2000")), ("1", None)],
+        );
+        let text = swapped.format() + "\n" + &standard.format();
+        let records = parse(&text).unwrap();
+        assert_eq!(records.len(), 2);
+        for (parsed, record) in records.iter().zip([&swapped, &standard]) {
+            assert_eq!(parsed.tags, record.tags);
+            assert_eq!(parsed.moves, record.moves);
+            assert_eq!(parsed.format(), record.format());
+        }
+        // the swap hands the first disc over without a new one
+        let states = records[0].replay().unwrap();
+        assert_eq!(states[2], (0, 1 << 3));
+        assert_eq!(records[0].variant().unwrap().name(), "most-fours second");
+        assert_eq!(records[1].replay().unwrap()[0], (0, 1));
+    }
+
+    #[test]
+    fn a_long_game_wraps() {
+        // the two bottom rows
+        let moves: Vec<(&str, Option<&str>)> = ["1", "2", "3", "4", "5", "6", "7"]
+            .iter()
+            .cycle()
+            .take(14)
+            .map(|text| (*text, Some("eval 1.0000")))
+            .collect();
+        let game = record(&[("Rules", "standard"), ("Result", "*")], &moves);
+        let text = game.format();
+        assert!(text.lines().all(|line| line.len() <= LINE_WIDTH));
+        assert_eq!(parse(&text).unwrap()[0].moves, game.moves);
+    }
+
+    #[test]
+    fn malformed_records() {
+        let error = |text: &str| parse(text).err().unwrap();
+        assert!(error("[Rules \"standard\"]\n\n1. 4 {eval").contains("unclosed comment"));
+        assert!(error("{eval 1.0} 4 *").contains("comment before the first move"));
+        assert!(error("[Rules \"standard\"\n\n1. 4 *").contains("invalid tag"));
+        assert_eq!(error("1. 4 4"), "the last game has no result");
+        assert!(error("1. 4 8 *").starts_with("game 1: illegal move 8 at ply 2"));
+        // the swap takes the swap rules
+        assert!(error("1. 4 s *").starts_with("game 1: illegal move s"));
+        assert!(error("[Variant \"most-fours\"]\n1. 4 *").contains("invalid variant"));
+        // four in the first column, then another move
+        let four = "1. 1 2 2. 1 2 3. 1 2 4. 1 2 1-0";
+        assert!(error(four).contains("move 8 comes after the end of the game"));
+        // which most fours plays on from
+        let most_fours = format!("[Variant \"most-fours draw\"]\n{four}");
+        assert_eq!(parse(&most_fours).unwrap()[0].replay().unwrap().len(), 9);
+    }
+}
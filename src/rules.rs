@@ -0,0 +1,225 @@
+// What ends a game and who wins it, the same for every game and search of a process:
+//
+//     --scoring first-four   the first four in a row wins (the standard rules)
+//     --scoring most-fours   the game goes on until the board is full, and whoever has more
+//                            fours in a row then wins, each line of four counting once (five in
+//                            a row are two)
+//     --full-board draw|first|second
+//                            what a full board without a winner is, equal counts of fours
+//                            included: a draw (the standard rules) or a win for the player
+//                            who moved first or second
+//
+// the exact solver, the opening book and the batch playouts only know the standard rules, so
+// the searches of the other variants do without them (with random playouts for the batches).
+// A game record's Variant tag says which rules it was played under, those its replay follows.
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use crate::board::{is_winning, FULL_GRID, STATUS};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Scoring {
+    FirstFour,
+    MostFours,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FullBoard {
+    Draw,
+    First,
+    Second,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Variant {
+    pub scoring: Scoring,
+    pub full_board: FullBoard,
+}
+
+pub const STANDARD: Variant = Variant {
+    scoring: Scoring::FirstFour,
+    full_board: FullBoard::Draw,
+};
+
+pub const SCORINGS: [(&str, Scoring); 2] = [
+    ("first-four", Scoring::FirstFour),
+    ("most-fours", Scoring::MostFours),
+];
+
+pub const FULL_BOARDS: [(&str, FullBoard); 3] = [
+    ("draw", FullBoard::Draw),
+    ("first", FullBoard::First),
+    ("second", FullBoard::Second),
+];
+
+// the indices in SCORINGS and FULL_BOARDS
+static SCORING: AtomicU8 = AtomicU8::new(0);
+static FULL_BOARD: AtomicU8 = AtomicU8::new(0);
+
+pub fn set(variant: Variant) {
+    let scoring = SCORINGS.iter().position(|(_, x)| *x == variant.scoring);
+    let full_board = FULL_BOARDS
+        .iter()
+        .position(|(_, x)| *x == variant.full_board);
+    SCORING.store(scoring.unwrap() as u8, Ordering::Relaxed);
+    FULL_BOARD.store(full_board.unwrap() as u8, Ordering::Relaxed);
+}
+
+pub fn current() -> Variant {
+    Variant {
+        scoring: SCORINGS[SCORING.load(Ordering::Relaxed) as usize].1,
+        full_board: FULL_BOARDS[FULL_BOARD.load(Ordering::Relaxed) as usize].1,
+    }
+}
+
+pub fn is_standard() -> bool {
+    current() == STANDARD
+}
+
+impl Variant {
+    // `most-fours second`, for the records
+    pub fn name(&self) -> String {
+        let scoring = SCORINGS.iter().find(|(_, x)| *x == self.scoring).unwrap().0;
+        let full_board = FULL_BOARDS
+            .iter()
+            .find(|(_, x)| *x == self.full_board)
+            .unwrap()
+            .0;
+        format!("{scoring} {full_board}")
+    }
+
+    // whether a game under these rules is over, which doesn't depend on whose turn it is
+    pub fn is_over(&self, p1: u64, p2: u64) -> bool {
+        let four = self.scoring == Scoring::FirstFour && (is_winning(p1) || is_winning(p2));
+        four || FULL_GRID == p1 | p2
+    }
+
+    // for the player to move (p1), `first` when they are the first player
+    pub fn status(&self, p1: u64, p2: u64, first: bool) -> STATUS {
+        if self.scoring == Scoring::FirstFour {
+            if is_winning(p1) {
+                return STATUS::WON;
+            }
+            if is_winning(p2) {
+                return STATUS::LOST;
+            }
+        }
+        if FULL_GRID == p1 | p2 {
+            return self.full_board(p1, p2, first);
+        }
+        STATUS::PLAYING
+    }
+
+    // the result of a full board, for the player to move (p1), `first` when they are the first
+    // player
+    pub fn full_board(&self, p1: u64, p2: u64, first: bool) -> STATUS {
+        if self.scoring == Scoring::MostFours {
+            let (mine, theirs) = (fours(p1), fours(p2));
+            if mine != theirs {
+                return if mine > theirs {
+                    STATUS::WON
+                } else {
+                    STATUS::LOST
+                };
+            }
+        }
+        match self.full_board {
+            FullBoard::Draw => STATUS::DRAW,
+            FullBoard::First if first => STATUS::WON,
+            FullBoard::Second if !first => STATUS::WON,
+            _ => STATUS::LOST,
+        }
+    }
+}
+
+// how many lines of four the player has
+pub fn fours(player: u64) -> u32 {
+    [1, 8, 9, 7]
+        .iter()
+        .map(|d| (player & player >> d & player >> (2 * d) & player >> (3 * d)).count_ones())
+        .sum()
+}
+
+// under the process' rules
+pub fn full_board(p1: u64, p2: u64, first: bool) -> STATUS {
+    current().full_board(p1, p2, first)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOST_FOURS: Variant = Variant {
+        scoring: Scoring::MostFours,
+        full_board: FullBoard::Draw,
+    };
+
+    // a full board, the cells `a` says are one player's and the others the other's
+    fn full(a: impl Fn(u32, u32) -> bool) -> (u64, u64) {
+        let mut state = (0, 0);
+        for row in 0..6 {
+            for column in 0..7 {
+                match a(row, column) {
+                    true => state.0 |= 1 << (row * 8 + column),
+                    false => state.1 |= 1 << (row * 8 + column),
+                }
+            }
+        }
+        state
+    }
+
+    // alternating along the rows, two rows at a time, so no one has a four
+    fn no_fours(row: u32, column: u32) -> bool {
+        (column + row / 2).is_multiple_of(2)
+    }
+
+    #[test]
+    fn the_first_four_ends_the_standard_game_only() {
+        // the player to move faces the other one's four on the bottom row
+        let (p1, p2) = (0b111 << 8, 0b1111);
+        assert!(STANDARD.is_over(p1, p2));
+        assert!(STANDARD.status(p1, p2, true) == STATUS::LOST);
+        assert!(STANDARD.status(p2, p1, false) == STATUS::WON);
+        assert!(!MOST_FOURS.is_over(p1, p2));
+        assert!(MOST_FOURS.status(p1, p2, true) == STATUS::PLAYING);
+    }
+
+    #[test]
+    fn full_boards_without_fours() {
+        let (p1, p2) = full(no_fours);
+        assert_eq!((fours(p1), fours(p2)), (0, 0));
+        assert!(STANDARD.is_over(p1, p2));
+        assert!(STANDARD.status(p1, p2, true) == STATUS::DRAW);
+        for scoring in [Scoring::FirstFour, Scoring::MostFours] {
+            let first = Variant {
+                scoring,
+                full_board: FullBoard::First,
+            };
+            assert!(first.status(p1, p2, true) == STATUS::WON);
+            assert!(first.status(p2, p1, false) == STATUS::LOST);
+            let second = Variant {
+                scoring,
+                full_board: FullBoard::Second,
+            };
+            assert!(second.status(p1, p2, true) == STATUS::LOST);
+            assert!(second.status(p2, p1, false) == STATUS::WON);
+        }
+    }
+
+    #[test]
+    fn most_fours_counts_the_lines() {
+        // the whole bottom row is four lines of four, whatever the full board rule says
+        let (p1, p2) = full(|row, column| row == 0 || no_fours(row, column));
+        assert_eq!((fours(p1), fours(p2)), (4, 0));
+        for full_board in [FullBoard::Draw, FullBoard::First, FullBoard::Second] {
+            let variant = Variant {
+                scoring: Scoring::MostFours,
+                full_board,
+            };
+            assert!(variant.is_over(p1, p2));
+            assert!(variant.status(p1, p2, false) == STATUS::WON);
+            assert!(variant.status(p2, p1, true) == STATUS::LOST);
+        }
+        // five in a row are two
+        assert_eq!(fours(0b11111), 2);
+    }
+}
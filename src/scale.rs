@@ -0,0 +1,28 @@
+// How evaluations are shown to people: the search's mean rewards go from 0 (a loss) to 2 (a
+// win), and everything printed for a reader goes through here to become the chances of the side
+// they're for, 0 to 100%, and in the game's grid mode a bar of them:
+//
+//     evaluation: [###############-----] 74.2%
+//
+// the machine formats (the protocol, the simple front-end, game records, analyze-file) keep
+// the raw value their readers parse.
+const BAR_WIDTH: usize = 20;
+
+// from 0 to 1
+pub fn probability(score: f64) -> f64 {
+    (score / 2.0).clamp(0.0, 1.0)
+}
+
+pub fn percent(score: f64) -> String {
+    format!("{:.1}%", 100.0 * probability(score))
+}
+
+pub fn bar(score: f64) -> String {
+    let filled = (probability(score) * BAR_WIDTH as f64).round() as usize;
+    format!(
+        "[{}{}] {}",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        percent(score)
+    )
+}
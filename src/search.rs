@@ -0,0 +1,161 @@
+// Searches that don't block whoever waits for them: search() runs mcst on a thread of its own
+// and returns a future of the result, which the gRPC server's runtime awaits (the text protocol
+// blocks on it with wait()). cancel(), or dropping the future, stops the search after its
+// current iteration, and the best move found so far is the result. Meanwhile progress() tells
+// how the root's moves stand, as of the last PROGRESS_EVERY iterations.
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    thread::{self, JoinHandle},
+};
+
+use crate::{mcst, random, solver::SolverCache, Limits, Node, Params, Rules};
+
+// one of the root's moves, as the search sees it
+#[derive(Clone)]
+pub struct RootChild {
+    pub state: (u64, u64),
+    pub visits: u32,
+    // from 0 to 2, for the player making the move
+    pub mean: f64,
+}
+
+pub type Progress = Arc<Mutex<Vec<RootChild>>>;
+
+pub fn root_children(graph: &[Node], root: usize) -> Vec<RootChild> {
+    graph[root]
+        .children()
+        .map(|child| RootChild {
+            state: graph[child].state,
+            visits: graph[child].nb_visit,
+            mean: graph[child].mean(),
+        })
+        .collect()
+}
+
+// what a search starts from, handed back with the result
+pub struct Position {
+    pub graph: Vec<Node>,
+    pub root: usize,
+    pub rules: Rules,
+    pub params: Params,
+    pub cache: SolverCache,
+}
+
+pub struct SearchResult {
+    pub score: f64,
+    pub best: (u64, u64),
+    // the tree the search grew, the root unchanged
+    pub position: Position,
+}
+
+#[derive(Default)]
+struct Shared {
+    result: Option<SearchResult>,
+    waker: Option<Waker>,
+}
+
+pub struct SearchFuture {
+    stop: Arc<AtomicBool>,
+    shared: Arc<Mutex<Shared>>,
+    progress: Progress,
+    thread: Option<JoinHandle<()>>,
+}
+
+// within the budget of `limits`, whose stop flag becomes the future's
+pub fn search(position: Position, limits: Limits) -> SearchFuture {
+    let stop = Arc::new(AtomicBool::new(false));
+    let shared = Arc::new(Mutex::new(Shared::default()));
+    let progress = Progress::default();
+    let limits = Limits {
+        stop: Some(stop.clone()),
+        ..limits
+    }
+    .with_progress(progress.clone());
+    let done = shared.clone();
+    // the search's generator follows from this thread's, for a --seed to repeat it
+    let seed = random::bits();
+    let thread = thread::spawn(move || {
+        random::seed(seed);
+        let Position {
+            graph,
+            root,
+            rules,
+            params,
+            mut cache,
+        } = position;
+        let (score, best, graph, _) = mcst(graph, root, &limits, rules, &params, &mut cache);
+        let position = Position {
+            graph,
+            root,
+            rules,
+            params,
+            cache,
+        };
+        let mut shared = done.lock().unwrap();
+        shared.result = Some(SearchResult {
+            score,
+            best,
+            position,
+        });
+        if let Some(waker) = shared.waker.take() {
+            waker.wake();
+        }
+    });
+    SearchFuture {
+        stop,
+        shared,
+        progress,
+        thread: Some(thread),
+    }
+}
+
+impl SearchFuture {
+    pub fn cancel(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn progress(&self) -> Vec<RootChild> {
+        self.progress.lock().unwrap().clone()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.thread.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    // blocks until the search is over, for callers without a runtime
+    pub fn wait(mut self) -> SearchResult {
+        let thread = self.thread.take().unwrap();
+        thread.join().expect("The search thread panicked");
+        self.shared.lock().unwrap().result.take().unwrap()
+    }
+}
+
+impl Future for SearchFuture {
+    type Output = SearchResult;
+
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<SearchResult> {
+        let mut shared = self.shared.lock().unwrap();
+        if let Some(result) = shared.result.take() {
+            return Poll::Ready(result);
+        }
+        // the result is set before the thread ends, unless it panicked
+        if self.thread.as_ref().is_some_and(JoinHandle::is_finished) {
+            panic!("The search thread panicked");
+        }
+        shared.waker = Some(context.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl Drop for SearchFuture {
+    // nobody is waiting for the result any more
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
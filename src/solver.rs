@@ -0,0 +1,124 @@
+use crate::{
+    board::{get_moves, is_winning, Board, FULL_GRID, STATUS},
+    eval::threats,
+    tt::Cache,
+};
+
+const BOTTOM_ROW: u64 = 0x7f;
+
+// what the threats alone prove for the player to move (p1), without looking ahead: a win when
+// one of theirs can be played, a loss when the opponent has two playable ones or one with
+// another right above it, PLAYING otherwise
+pub fn immediate_status(p1: u64, p2: u64) -> STATUS {
+    let grid = p1 | p2;
+    // the lowest empty cell of each column
+    let playable = (grid << 8 | BOTTOM_ROW) & !grid & FULL_GRID;
+    if threats(p1, grid) & playable != 0 {
+        return STATUS::WON;
+    }
+    let theirs = threats(p2, grid);
+    if (theirs & playable).count_ones() >= 2 || theirs & playable & theirs >> 8 != 0 {
+        return STATUS::LOST;
+    }
+    STATUS::PLAYING
+}
+
+// whether the player to move (p1) can force a win within `plies` moves of both players
+pub fn wins_within(p1: u64, p2: u64, plies: u32) -> bool {
+    if plies == 0 {
+        return false;
+    }
+    let moves = get_moves(p1, p2);
+    if moves.iter().any(|(_, player)| is_winning(*player)) {
+        return true;
+    }
+    plies >= 3
+        && moves
+            .iter()
+            .any(|(q1, q2)| loses_within(*q1, *q2, plies - 1))
+}
+
+// whether every move of the player to move (p1) lets the opponent force a win within `plies`
+pub fn loses_within(p1: u64, p2: u64, plies: u32) -> bool {
+    if plies < 2 {
+        return false;
+    }
+    let moves = get_moves(p1, p2);
+    !moves.is_empty()
+        && moves
+            .iter()
+            .all(|(q1, q2)| !is_winning(*q2) && wins_within(*q1, *q2, plies - 1))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Bound {
+    Exact,
+    // the score is at least this
+    Lower,
+    // the score is at most this
+    Upper,
+}
+
+pub type SolverCache = Cache<(i8, Bound)>;
+
+// center columns first, they make the cutoffs come sooner
+const COLUMN_ORDER: [u32; 7] = [4, 3, 5, 2, 6, 1, 7];
+
+// the proven status matching a score of solve()
+pub fn status(score: i32) -> STATUS {
+    match score {
+        score if score > 0 => STATUS::WON,
+        score if score < 0 => STATUS::LOST,
+        _ => STATUS::DRAW,
+    }
+}
+
+// exact result for the player to move (p1): positive for a win, negative for a loss, 0 for a draw,
+// the further from 0 the sooner it happens (the number of empty cells left when it does, plus one)
+pub fn solve(p1: u64, p2: u64, cache: &mut SolverCache) -> i32 {
+    negamax(Board::new(p1, p2), -64, 64, cache)
+}
+
+fn negamax(board: Board, mut alpha: i32, mut beta: i32, cache: &mut SolverCache) -> i32 {
+    let (p1, p2) = board.state();
+    let empty = 42 - (p1 | p2).count_ones() as i32;
+    let moves = get_moves(p1, p2);
+    if moves.is_empty() {
+        return 0;
+    }
+    if moves.iter().any(|(_, player)| is_winning(*player)) {
+        return empty;
+    }
+    let original_alpha = alpha;
+    if let Some((value, bound)) = cache.get(board.hash()) {
+        let value = value as i32;
+        match bound {
+            Bound::Exact => return value,
+            Bound::Lower => alpha = alpha.max(value),
+            Bound::Upper => beta = beta.min(value),
+        }
+        if alpha >= beta {
+            return value;
+        }
+    }
+    let mut best = -64;
+    for column in COLUMN_ORDER {
+        let Some(child) = board.play(column) else {
+            continue;
+        };
+        best = best.max(-negamax(child, -beta, -alpha, cache));
+        alpha = alpha.max(best);
+        if alpha >= beta {
+            break;
+        }
+    }
+    let bound = if best <= original_alpha {
+        Bound::Upper
+    } else if best >= beta {
+        Bound::Lower
+    } else {
+        Bound::Exact
+    };
+    cache.insert(board.hash(), (best as i8, bound));
+    best
+}
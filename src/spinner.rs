@@ -0,0 +1,110 @@
+// A bar filling up with the bot's budget while it thinks, its time or, with --iterations, its
+// iterations, so a slow setting doesn't look like a hang. It is redrawn ten times a second at
+// most, on its own line, and wiped before the move is printed; the reports of long searches
+// go above it with println.
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::search;
+
+const FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+const WIDTH: usize = 20;
+const REDRAW: Duration = Duration::from_millis(100);
+
+// what the bar measures the search against
+pub enum Budget {
+    // milliseconds
+    Time(u128),
+    // the iterations, counted by the visits of the root's moves in the progress the search
+    // keeps, from those it starts with
+    Iterations(u64, search::Progress),
+}
+
+pub struct Spinner {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+fn visits(progress: &search::Progress) -> u64 {
+    let children = progress.lock().unwrap();
+    children.iter().map(|child| child.visits as u64).sum()
+}
+
+// `done` out of `total`, told as `text`
+fn draw(frame: usize, done: f64, total: f64, text: &str) {
+    let filled = (WIDTH as f64 * done / total.max(0.001)).min(WIDTH as f64) as usize;
+    print!(
+        "\rthinking {} [{}{}] {text}",
+        FRAMES[frame % FRAMES.len()],
+        "#".repeat(filled),
+        ".".repeat(WIDTH - filled)
+    );
+    io::stdout().flush().ok();
+}
+
+// a line that goes above the bar, which the next redraw puts back under it
+pub fn println(line: &str) {
+    match io::stdout().is_terminal() {
+        true => println!("\r\x1b[K{line}"),
+        false => println!("{line}"),
+    }
+}
+
+impl Spinner {
+    // nothing is drawn when the output isn't a terminal
+    pub fn start(budget: Budget) -> Spinner {
+        let stop = Arc::new(AtomicBool::new(false));
+        if !io::stdout().is_terminal() {
+            return Spinner { stop, thread: None };
+        }
+        let stopped = stop.clone();
+        // before the search is under way
+        let before = match &budget {
+            Budget::Iterations(_, progress) => visits(progress),
+            Budget::Time(_) => 0,
+        };
+        let thread = thread::spawn(move || {
+            let start = Instant::now();
+            let mut frame = 0;
+            while !stopped.load(Ordering::Relaxed) {
+                match &budget {
+                    Budget::Time(time) => {
+                        let time = *time as f64 / 1000.0;
+                        let elapsed = start.elapsed().as_secs_f64().min(time);
+                        let text = format!("{elapsed:.1}s / {time:.1}s");
+                        draw(frame, elapsed, time, &text);
+                    }
+                    Budget::Iterations(total, progress) => {
+                        let done = visits(progress).saturating_sub(before).min(*total);
+                        let text = format!("{done} / {total} iterations");
+                        draw(frame, done as f64, *total as f64, &text);
+                    }
+                }
+                frame += 1;
+                thread::park_timeout(REDRAW);
+            }
+            // the line goes away for the move
+            print!("\r\x1b[K");
+            io::stdout().flush().ok();
+        });
+        Spinner {
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread {
+            thread.thread().unpark();
+            thread.join().ok();
+        }
+    }
+}
@@ -0,0 +1,258 @@
+// bench-storage [ITERATIONS] [SEED]: the same plain UCT search run over the usual Vec<Node>
+// arena and over a structure of arrays, where the statistics the selection scans are packed
+// together instead of being spread over whole nodes
+use std::{ops::Range, time::Instant};
+
+use crate::{
+    board::{first_to_move, get_moves, get_status, STATUS},
+    compare, get_profile, random, simulation,
+    solver::SolverCache,
+    suite, usage_error, Node,
+};
+
+const DEFAULT_ITERATIONS: u64 = 200_000;
+const DEFAULT_SEED: u64 = 1;
+
+// what the search needs from a tree, whatever its layout, `first` being whether the first
+// player is to move in the positions
+pub trait TreeStorage {
+    fn with_root(state: (u64, u64), first: bool) -> Self;
+    fn len(&self) -> usize;
+    fn state(&self, node: usize) -> (u64, u64);
+    fn status(&self, node: usize) -> &STATUS;
+    fn parent(&self, node: usize) -> Option<usize>;
+    fn children(&self, node: usize) -> Range<usize>;
+    fn visits(&self, node: usize) -> u32;
+    fn score(&self, node: usize) -> f32;
+    // appends the children after the last node
+    fn expand(&mut self, node: usize, moves: &[(u64, u64)], first: bool);
+    fn add_result(&mut self, node: usize, score: f32);
+}
+
+impl TreeStorage for Vec<Node> {
+    fn with_root(state: (u64, u64), first: bool) -> Self {
+        let status = get_status(state.0, state.1, first);
+        vec![Node::new(state, None, status, false, first)]
+    }
+
+    fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    fn state(&self, node: usize) -> (u64, u64) {
+        self[node].state
+    }
+
+    fn status(&self, node: usize) -> &STATUS {
+        &self[node].status
+    }
+
+    fn parent(&self, node: usize) -> Option<usize> {
+        self[node].parent()
+    }
+
+    fn children(&self, node: usize) -> Range<usize> {
+        self[node].children()
+    }
+
+    fn visits(&self, node: usize) -> u32 {
+        self[node].nb_visit
+    }
+
+    fn score(&self, node: usize) -> f32 {
+        self[node].score
+    }
+
+    fn expand(&mut self, node: usize, moves: &[(u64, u64)], first: bool) {
+        self[node].first_child = self.as_slice().len() as u32;
+        self[node].nb_children = moves.len() as u8;
+        for state in moves {
+            let status = get_status(state.0, state.1, first);
+            self.push(Node::new(*state, Some(node), status, false, first));
+        }
+    }
+
+    fn add_result(&mut self, node: usize, score: f32) {
+        self[node].nb_visit += 1;
+        self[node].score += score;
+    }
+}
+
+#[derive(Default)]
+pub struct SoaTree {
+    states: Vec<(u64, u64)>,
+    statuses: Vec<STATUS>,
+    parents: Vec<u32>,
+    first_children: Vec<u32>,
+    nb_children: Vec<u8>,
+    visits: Vec<u32>,
+    scores: Vec<f32>,
+}
+
+impl SoaTree {
+    fn push(&mut self, state: (u64, u64), parent: u32, first: bool) {
+        self.states.push(state);
+        self.statuses.push(get_status(state.0, state.1, first));
+        self.parents.push(parent);
+        self.first_children.push(0);
+        self.nb_children.push(0);
+        self.visits.push(0);
+        self.scores.push(0.0);
+    }
+}
+
+impl TreeStorage for SoaTree {
+    fn with_root(state: (u64, u64), first: bool) -> Self {
+        let mut tree = SoaTree::default();
+        // the root is its own parent, the index can't be anything else
+        tree.push(state, 0, first);
+        tree
+    }
+
+    fn len(&self) -> usize {
+        self.states.len()
+    }
+
+    fn state(&self, node: usize) -> (u64, u64) {
+        self.states[node]
+    }
+
+    fn status(&self, node: usize) -> &STATUS {
+        &self.statuses[node]
+    }
+
+    fn parent(&self, node: usize) -> Option<usize> {
+        (node != 0).then_some(self.parents[node] as usize)
+    }
+
+    fn children(&self, node: usize) -> Range<usize> {
+        let first = self.first_children[node] as usize;
+        first..first + self.nb_children[node] as usize
+    }
+
+    fn visits(&self, node: usize) -> u32 {
+        self.visits[node]
+    }
+
+    fn score(&self, node: usize) -> f32 {
+        self.scores[node]
+    }
+
+    fn expand(&mut self, node: usize, moves: &[(u64, u64)], first: bool) {
+        self.first_children[node] = self.len() as u32;
+        self.nb_children[node] = moves.len() as u8;
+        for state in moves {
+            self.push(*state, node as u32, first);
+        }
+    }
+
+    fn add_result(&mut self, node: usize, score: f32) {
+        self.visits[node] += 1;
+        self.scores[node] += score;
+    }
+}
+
+// descends with ucb1 to a node without statistics, creating the children on the way, and
+// tells whether the first player is to move there
+fn select<T: TreeStorage>(tree: &mut T, uctc: f32, mut first: bool) -> (usize, bool) {
+    let mut node = 0;
+    loop {
+        if *tree.status(node) != STATUS::PLAYING {
+            return (node, first);
+        }
+        first = !first;
+        if tree.children(node).is_empty() {
+            let (p1, p2) = tree.state(node);
+            tree.expand(node, &get_moves(p1, p2), first);
+        }
+        let log_visits = (tree.visits(node) as f32).log2();
+        let mut best = None;
+        let mut best_value = f32::MIN;
+        for child in tree.children(node) {
+            let visits = tree.visits(child) as f32;
+            if visits == 0.0 {
+                return (child, first);
+            }
+            let value = tree.score(child) / visits + uctc * (log_visits / visits).sqrt();
+            if value > best_value {
+                best_value = value;
+                best = Some(child);
+            }
+        }
+        node = best.unwrap();
+    }
+}
+
+// the search time and the part of it that wasn't spent in playouts, in milliseconds
+fn search<T: TreeStorage>(state: (u64, u64), first: bool, iterations: u64) -> (T, f64, f64) {
+    let params = get_profile("default").unwrap();
+    let mut tree = T::with_root(state, first);
+    let mut playouts = 0.0;
+    // the default profile's playouts don't ask the solver
+    let mut cache = SolverCache::new(0);
+    let now = Instant::now();
+    for _ in 0..iterations {
+        let (node, first) = select(&mut tree, params.uctc as f32, first);
+        let (p1, p2) = tree.state(node);
+        let playout = Instant::now();
+        let mut score = match tree.status(node) {
+            STATUS::LOST => 2.0,
+            STATUS::DRAW => 1.0,
+            _ => simulation(p1, p2, first, 1.0, &params, &mut cache) as f32,
+        };
+        playouts += playout.elapsed().as_secs_f64();
+        let mut node = Some(node);
+        while let Some(current) = node {
+            tree.add_result(current, score);
+            score = 2.0 - score;
+            node = tree.parent(current);
+        }
+    }
+    let total = now.elapsed().as_secs_f64();
+    (tree, 1000.0 * total, 1000.0 * (total - playouts))
+}
+
+fn bench<T: TreeStorage>(name: &str, iterations: u64, seed: u64) -> Vec<usize> {
+    let mut total = 0.0;
+    let mut tree_time = 0.0;
+    let mut choices = vec![];
+    for (i, moves) in compare::POSITIONS.iter().enumerate() {
+        let columns: Vec<u32> = moves.chars().filter_map(|c| c.to_digit(10)).collect();
+        random::seed(seed + i as u64);
+        let state = suite::replay(&columns).unwrap();
+        let (tree, time, in_tree) = search::<T>(state, first_to_move(columns.len()), iterations);
+        total += time;
+        tree_time += in_tree;
+        choices.push(
+            tree.children(0)
+                .max_by_key(|child| tree.visits(*child))
+                .unwrap(),
+        );
+        if i == 0 {
+            println!("{name}: {} nodes in the first tree", tree.len());
+        }
+    }
+    println!("{name}: {total:.0} ms in all, {tree_time:.0} ms of it outside the playouts");
+    choices
+}
+
+pub fn main(args: &[String]) {
+    let parse = |i: usize, default: u64| match args.get(i) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| usage_error("bench-storage expects numbers")),
+        None => default,
+    };
+    let iterations = parse(0, DEFAULT_ITERATIONS);
+    let seed = parse(1, DEFAULT_SEED);
+    println!(
+        "{} positions, {iterations} iterations each, seed {seed}",
+        compare::POSITIONS.len()
+    );
+    let nodes = bench::<Vec<Node>>("Vec<Node>", iterations, seed);
+    let arrays = bench::<SoaTree>("structure of arrays", iterations, seed);
+    // same seed, same algorithm: only the layout may differ
+    if nodes != arrays {
+        println!("warning: the two layouts didn't pick the same moves");
+    }
+}
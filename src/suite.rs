@@ -0,0 +1,88 @@
+// Test suites are text files with one position per line:
+//
+//     <moves> bm <column> [; comment]
+//
+// <moves> are the columns (1 -> 7) played from the empty board and <column> the best
+// move for the player to move, so `4453 bm 3 ; wins in 5 plies` asks for column 3
+// after 4, 4, 5 and 3 were played. Empty lines and lines starting with # are ignored.
+use std::fs;
+
+use crate::board::{is_over, play_column};
+
+pub struct Entry {
+    pub moves: Vec<u32>,
+    pub best: u32,
+    pub comment: String,
+}
+
+// None if a move is illegal or comes after the end of the game
+pub fn replay(moves: &[u32]) -> Option<(u64, u64)> {
+    let mut state = (0, 0);
+    for column in moves {
+        if is_over(state.0, state.1) {
+            return None;
+        }
+        state = play_column(state.0, state.1, *column)?;
+    }
+    Some(state)
+}
+
+pub fn moves_to_string(moves: &[u32]) -> String {
+    moves.iter().map(|column| column.to_string()).collect()
+}
+
+fn parse_line(line: &str) -> Option<Entry> {
+    let (position, comment) = line.split_once(';').unwrap_or((line, ""));
+    let fields: Vec<&str> = position.split_whitespace().collect();
+    let (moves, best) = match fields[..] {
+        [moves, "bm", best] => (moves, best),
+        ["bm", best] => ("", best),
+        _ => return None,
+    };
+    let moves = moves
+        .chars()
+        .map(|c| c.to_digit(10))
+        .collect::<Option<Vec<u32>>>()?;
+    replay(&moves)?;
+    Some(Entry {
+        moves,
+        best: best
+            .parse()
+            .ok()
+            .filter(|column| (1..=7).contains(column))?,
+        comment: comment.trim().to_string(),
+    })
+}
+
+pub fn format_entry(entry: &Entry) -> String {
+    let mut line = format!("{} bm {}", moves_to_string(&entry.moves), entry.best);
+    if !entry.comment.is_empty() {
+        line += &format!(" ; {}", entry.comment);
+    }
+    line.trim_start().to_string()
+}
+
+pub fn load(path: &str) -> Result<Vec<Entry>, String> {
+    let content =
+        fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}"))?;
+    let mut entries = vec![];
+    for (n, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line) {
+            Some(entry) => entries.push(entry),
+            None => return Err(format!("{path}:{}: invalid test suite line", n + 1)),
+        }
+    }
+    Ok(entries)
+}
+
+pub fn save(path: &str, entries: &[Entry]) -> Result<(), String> {
+    let content: String = entries
+        .iter()
+        .map(|entry| format_entry(entry) + "\n")
+        .collect();
+    fs::write(path, content).map_err(|error| format!("can't write {path}: {error}"))
+}
@@ -0,0 +1,87 @@
+// what the engine thought of the game as it went: its winning chances after every ply, drawn
+// as an ascii graph once the game is over and appended as a json line to a telemetry file:
+//
+//     {"date":"2026.10.14","first":"human","second":"mcst_connect4 default 1000ms",
+//      "moves":["4","4","3"],"result":"0-1","win_probability":[0.52,0.55,0.61]}
+//
+// (on one line), the probabilities being the engine's, from 0 to 1.
+use std::{fs::OpenOptions, io::Write};
+
+use crate::record::Record;
+
+const GRAPH_HEIGHT: usize = 9;
+
+// one column per ply, 100% at the top
+pub fn graph(probabilities: &[f64]) -> String {
+    let mut rows = vec![];
+    for row in (0..GRAPH_HEIGHT).rev() {
+        let label = match row {
+            r if r == GRAPH_HEIGHT - 1 => "100%",
+            r if r == GRAPH_HEIGHT / 2 => " 50%",
+            0 => "  0%",
+            _ => "    ",
+        };
+        let line: String = probabilities
+            .iter()
+            .map(|p| {
+                if (p * (GRAPH_HEIGHT - 1) as f64).round() as usize == row {
+                    '*'
+                } else if row == GRAPH_HEIGHT / 2 {
+                    '-'
+                } else {
+                    ' '
+                }
+            })
+            .collect();
+        rows.push(format!("{label} |{}", line.trim_end()));
+    }
+    // a mark every 10 plies
+    let axis: String = (1..=probabilities.len())
+        .map(|ply| if ply % 10 == 0 { '|' } else { '-' })
+        .collect();
+    rows.push(format!("     +{axis}"));
+    rows.join("\n")
+}
+
+pub fn json_string(text: &str) -> String {
+    let mut escaped = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped += "\\\"",
+            '\\' => escaped += "\\\\",
+            c if (c as u32) < 0x20 => escaped += &format!("\\u{:04x}", c as u32),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+pub fn to_json(game: &Record, probabilities: &[f64]) -> String {
+    let tag = |name| json_string(game.tag(name).unwrap_or(""));
+    let moves: Vec<String> = game
+        .moves
+        .iter()
+        .map(|(text, _)| json_string(text))
+        .collect();
+    let probabilities: Vec<String> = probabilities.iter().map(|p| format!("{p:.4}")).collect();
+    format!(
+        "{{\"date\":{},\"first\":{},\"second\":{},\"moves\":[{}],\"result\":{},\"win_probability\":[{}]}}",
+        tag("Date"),
+        tag("First"),
+        tag("Second"),
+        moves.join(","),
+        tag("Result"),
+        probabilities.join(",")
+    )
+}
+
+pub fn append(path: &str, game: &Record, probabilities: &[f64]) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|error| format!("can't write {path}: {error}"))?;
+    writeln!(file, "{}", to_json(game, probabilities))
+        .map_err(|error| format!("can't write {path}: {error}"))
+}
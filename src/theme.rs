@@ -0,0 +1,112 @@
+// How the grid is drawn: the discs, empty cells, the winning four and ANSI colors. --theme
+// takes one of the built-in plain, high-contrast and emoji themes or a theme file, with one
+// setting per line, empty lines and lines starting with # being ignored:
+//
+//     first 🔴
+//     second 🟡
+//     empty ⚪
+//     four ⭐
+//     first-color 1;31
+//     second-color 1;33
+//
+// first and second are the discs of the first and the second player (X and O, --symbols
+// having the last word), the colors are SGR codes and the four is drawn in its owner's
+// color. Left out settings keep the plain theme's.
+use std::fs;
+
+use crate::board::{Symbols, SYMBOLS};
+
+#[derive(Clone)]
+pub struct Theme {
+    pub symbols: Symbols,
+    pub empty: char,
+    pub four: char,
+    // the first and the second player's
+    pub colors: [Option<String>; 2],
+}
+
+impl Theme {
+    pub fn plain() -> Theme {
+        Theme {
+            symbols: SYMBOLS,
+            empty: '_',
+            four: '#',
+            colors: [None, None],
+        }
+    }
+
+    // a cell of the first (0) or the second (1) player
+    pub fn paint(&self, seat: usize, glyph: char) -> String {
+        match &self.colors[seat] {
+            Some(code) => format!("\x1b[{code}m{glyph}\x1b[0m"),
+            None => glyph.to_string(),
+        }
+    }
+}
+
+fn by_name(name: &str) -> Option<Theme> {
+    let plain = Theme::plain();
+    match name {
+        "plain" => Some(plain),
+        "high-contrast" => Some(Theme {
+            empty: '.',
+            colors: [Some("1;97;41".to_string()), Some("1;30;103".to_string())],
+            ..plain
+        }),
+        "emoji" => Some(Theme {
+            symbols: Symbols {
+                first: '🔴',
+                second: '🟡',
+            },
+            empty: '⚪',
+            four: '⭐',
+            ..plain
+        }),
+        _ => None,
+    }
+}
+
+fn parse(text: &str) -> Result<Theme, String> {
+    let mut theme = Theme::plain();
+    let glyph = |value: &str, line: usize| {
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(format!("line {line}: one character expected, got {value}")),
+        }
+    };
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("line {}: no value for {line}", i + 1))?;
+        let value = value.trim();
+        match key {
+            "first" => theme.symbols.first = glyph(value, i + 1)?,
+            "second" => theme.symbols.second = glyph(value, i + 1)?,
+            "empty" => theme.empty = glyph(value, i + 1)?,
+            "four" => theme.four = glyph(value, i + 1)?,
+            "first-color" => theme.colors[0] = Some(value.to_string()),
+            "second-color" => theme.colors[1] = Some(value.to_string()),
+            _ => return Err(format!("line {}: unknown setting {key}", i + 1)),
+        }
+    }
+    let Symbols { first, second } = theme.symbols;
+    if first == second || [first, second].contains(&theme.empty) {
+        return Err("the discs and empty cells need different characters".to_string());
+    }
+    Ok(theme)
+}
+
+// a built-in theme, or else a theme file
+pub fn load(name: &str) -> Result<Theme, String> {
+    if let Some(theme) = by_name(name) {
+        return Ok(theme);
+    }
+    let content =
+        fs::read_to_string(name).map_err(|error| format!("can't read {name}: {error}"))?;
+    parse(&content).map_err(|error| format!("{name}: {error}"))
+}
@@ -0,0 +1,70 @@
+// How the time of a move depends on how far the game is: --time-curve FILE scales the bot's
+// time (and a plain protocol go's) by the factor of the ply about to be played, the first move
+// being ply 1 and plies no line covers keeping the time as it is. One range a line, empty lines
+// and lines starting with # being ignored:
+//
+//     # the middle game is where it's won, the end mostly forced
+//     8-20 1.5
+//     21 1.2
+//     33-42 0.5
+//
+// the first line covering a ply having the last word.
+use std::fs;
+
+#[derive(Clone, Default)]
+pub struct Curve {
+    // the first and last plies, and their factor
+    ranges: Vec<(u32, u32, f64)>,
+}
+
+impl Curve {
+    pub fn factor(&self, ply: u32) -> f64 {
+        self.ranges
+            .iter()
+            .find(|(first, last, _)| (*first..=*last).contains(&ply))
+            .map_or(1.0, |(_, _, factor)| *factor)
+    }
+
+    // the time for the ply, searches without a time limit keeping theirs
+    pub fn time(&self, base: u128, ply: u32) -> u128 {
+        if base == u128::MAX {
+            return base;
+        }
+        (base as f64 * self.factor(ply)).round() as u128
+    }
+}
+
+fn parse_plies(text: &str) -> Option<(u32, u32)> {
+    let (first, last) = text.split_once('-').unwrap_or((text, text));
+    let (first, last) = (first.parse().ok()?, last.parse().ok()?);
+    (1 <= first && first <= last).then_some((first, last))
+}
+
+fn parse(text: &str) -> Result<Curve, String> {
+    let mut curve = Curve::default();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (plies, factor) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("line {}: no factor for {line}", i + 1))?;
+        let (first, last) =
+            parse_plies(plies).ok_or_else(|| format!("line {}: invalid plies {plies}", i + 1))?;
+        let factor = factor
+            .trim()
+            .parse()
+            .ok()
+            .filter(|factor: &f64| *factor > 0.0 && factor.is_finite())
+            .ok_or_else(|| format!("line {}: invalid factor {}", i + 1, factor.trim()))?;
+        curve.ranges.push((first, last, factor));
+    }
+    Ok(curve)
+}
+
+pub fn load(path: &str) -> Result<Curve, String> {
+    let content =
+        fs::read_to_string(path).map_err(|error| format!("can't read {path}: {error}"))?;
+    parse(&content).map_err(|error| format!("{path}: {error}"))
+}
@@ -0,0 +1,209 @@
+use std::{collections::HashMap, mem::size_of};
+
+// a transposition table keyed by zobrist hash that never grows past its capacity,
+// the least recently used entry makes room for the new ones
+pub const DEFAULT_MB: usize = 16;
+
+const NONE: usize = usize::MAX;
+
+struct Slot<V> {
+    key: u64,
+    value: V,
+    // towards the most recently used entry
+    prev: usize,
+    // towards the least recently used entry
+    next: usize,
+}
+
+pub struct Cache<V> {
+    slots: Vec<Slot<V>>,
+    index: HashMap<u64, usize>,
+    capacity: usize,
+    // most recently used
+    head: usize,
+    // least recently used, the next one to go
+    tail: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl<V: Copy> Cache<V> {
+    pub fn new(megabytes: usize) -> Cache<V> {
+        let capacity = (megabytes << 20) / Cache::<V>::entry_size();
+        Cache {
+            slots: Vec::new(),
+            index: HashMap::new(),
+            capacity,
+            head: NONE,
+            tail: NONE,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    // the slot plus the hash map's bucket and control byte
+    pub fn entry_size() -> usize {
+        size_of::<Slot<V>>() + size_of::<(u64, usize)>() + 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    // bytes taken by the entries so far
+    pub fn memory(&self) -> usize {
+        self.len() * Cache::<V>::entry_size()
+    }
+
+    pub fn get(&mut self, key: u64) -> Option<V> {
+        match self.index.get(&key) {
+            Some(&slot) => {
+                self.hits += 1;
+                self.unlink(slot);
+                self.push_front(slot);
+                Some(self.slots[slot].value)
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: u64, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some(&slot) = self.index.get(&key) {
+            self.slots[slot].value = value;
+            self.unlink(slot);
+            self.push_front(slot);
+            return;
+        }
+        let slot = if self.slots.len() < self.capacity {
+            self.slots.push(Slot {
+                key,
+                value,
+                prev: NONE,
+                next: NONE,
+            });
+            self.slots.len() - 1
+        } else {
+            // the oldest entry's slot is reused as is
+            let slot = self.tail;
+            self.unlink(slot);
+            self.index.remove(&self.slots[slot].key);
+            self.slots[slot].key = key;
+            self.slots[slot].value = value;
+            self.evictions += 1;
+            slot
+        };
+        self.index.insert(key, slot);
+        self.push_front(slot);
+    }
+
+    pub fn summary(&self) -> String {
+        let lookups = self.hits + self.misses;
+        let rate = if lookups == 0 {
+            0.0
+        } else {
+            100.0 * self.hits as f64 / lookups as f64
+        };
+        format!(
+            "{} hits, {} misses ({rate:.1}% hit), {}/{} entries, {} evicted",
+            self.hits,
+            self.misses,
+            self.len(),
+            self.capacity,
+            self.evictions
+        )
+    }
+
+    fn unlink(&mut self, slot: usize) {
+        let (prev, next) = (self.slots[slot].prev, self.slots[slot].next);
+        if prev == NONE {
+            self.head = next;
+        } else {
+            self.slots[prev].next = next;
+        }
+        if next == NONE {
+            self.tail = prev;
+        } else {
+            self.slots[next].prev = prev;
+        }
+    }
+
+    fn push_front(&mut self, slot: usize) {
+        self.slots[slot].prev = NONE;
+        self.slots[slot].next = self.head;
+        if self.head == NONE {
+            self.tail = slot;
+        } else {
+            self.slots[self.head].prev = slot;
+        }
+        self.head = slot;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small(capacity: usize) -> Cache<u32> {
+        let mut cache = Cache::new(1);
+        cache.capacity = capacity;
+        cache
+    }
+
+    #[test]
+    fn least_recently_used_goes_first() {
+        let mut cache = small(3);
+        for key in 1..=3 {
+            cache.insert(key, key as u32);
+        }
+        // 1 is used again, which leaves 2 the oldest
+        assert_eq!(cache.get(1), Some(1));
+        cache.insert(4, 4);
+        assert_eq!(cache.get(2), None);
+        cache.insert(5, 5);
+        assert_eq!(cache.get(3), None);
+        assert_eq!(
+            (cache.get(1), cache.get(4), cache.get(5)),
+            (Some(1), Some(4), Some(5))
+        );
+        assert_eq!((cache.len(), cache.evictions), (3, 2));
+    }
+
+    #[test]
+    fn hits_and_replacements() {
+        let mut cache = small(2);
+        assert_eq!(cache.get(7), None);
+        cache.insert(7, 1);
+        cache.insert(7, 2);
+        assert_eq!(cache.get(7), Some(2));
+        assert_eq!((cache.hits, cache.misses, cache.len()), (1, 1, 1));
+        // replacing an entry makes it the most recent one
+        cache.insert(8, 8);
+        cache.insert(7, 3);
+        cache.insert(9, 9);
+        assert_eq!((cache.get(8), cache.get(7)), (None, Some(3)));
+        assert_eq!(cache.evictions, 1);
+    }
+
+    #[test]
+    fn byte_budget() {
+        let mut cache = Cache::<u32>::new(1);
+        let capacity = (1 << 20) / Cache::<u32>::entry_size();
+        for key in 0..2 * capacity as u64 {
+            cache.insert(key, 0);
+        }
+        assert_eq!(cache.len(), capacity);
+        assert!(cache.memory() <= 1 << 20);
+        // no room at all, nothing is kept
+        let mut empty = Cache::<u32>::new(0);
+        empty.insert(1, 1);
+        assert_eq!((empty.len(), empty.get(1)), (0, None));
+    }
+}